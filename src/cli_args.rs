@@ -1,33 +1,46 @@
-use clap::{command, value_parser, Arg, ArgMatches};
+use std::{fs, time::Duration};
+
+use clap::{command, value_parser, Arg, ArgAction, ArgGroup, ArgMatches};
+
+use crate::reporters::OutputFormat;
+use crate::stages::StagePlan;
+use crate::thresholds::Threshold;
+use crate::tls::TlsOptions;
+use crate::url_parser::parse_header;
+use crate::utils::parse_duration;
 
 pub struct CliArgs {
     pub threads: u8,
-    pub header: Option<String>,
+    pub headers: Vec<String>,
+    pub default_headers: bool,
     pub connections: usize,
-    pub duration: usize,
+    pub duration: Option<usize>,
+    pub requests: Option<u64>,
+    pub rate: Option<u32>,
+    pub interval: Duration,
+    pub warmup: Duration,
+    pub stages: Option<StagePlan>,
+    pub timeout: Option<Duration>,
+    pub show_warmup: bool,
+    pub keep_alive: bool,
+    pub max_requests_per_conn: Option<u64>,
+    pub pipeline: usize,
+    pub tls_options: TlsOptions,
+    pub method: String,
+    pub body: Option<Vec<u8>>,
+    pub binary_body: bool,
+    pub csv_file: Option<String>,
+    pub scenario_file: Option<String>,
+    pub output_format: OutputFormat,
+    pub output_file: Option<String>,
+    pub timeseries_file: Option<String>,
+    pub junit_file: Option<String>,
+    pub thresholds: Vec<String>,
     pub url: String,
 }
 
-impl CliArgs {
-    pub fn new(
-        threads: u8,
-        header: Option<String>,
-        connections: usize,
-        duration: usize,
-        url: String,
-    ) -> Self {
-        return CliArgs {
-            threads,
-            header,
-            connections,
-            duration,
-            url,
-        };
-    }
-}
-
 pub fn parse_cli_arguments() -> ArgMatches {
-    let parsed_args = command!()
+    command!()
         .arg(
             Arg::new("threads")
                 .short('t')
@@ -38,8 +51,15 @@ pub fn parse_cli_arguments() -> ArgMatches {
         .arg(
             Arg::new("header")
                 .short('H')
-                .help("header to send with request")
-                .value_parser(value_parser!(String)),
+                .help("header to send with request, can be repeated")
+                .action(ArgAction::Append)
+                .value_parser(|header: &str| parse_header(header).map(|_| header.to_string())),
+        )
+        .arg(
+            Arg::new("no-default-headers")
+                .long("no-default-headers")
+                .help("do not send default headers such as Host")
+                .action(ArgAction::SetTrue),
         )
         .arg(
             Arg::new("conns")
@@ -52,32 +72,273 @@ pub fn parse_cli_arguments() -> ArgMatches {
             Arg::new("duration")
                 .short('d')
                 .help("how long to test in seconds")
-                .required(true)
+                .required_unless_present_any(["stages", "requests"])
                 .value_parser(value_parser!(usize)),
         )
+        .arg(
+            Arg::new("requests")
+                .short('n')
+                .help("stop after this many responses, split evenly across threads and connections")
+                .conflicts_with("stages")
+                .value_parser(value_parser!(u64).range(1..)),
+        )
+        .arg(
+            Arg::new("rate")
+                .short('R')
+                .help("constant throughput in requests per second across all threads")
+                .value_parser(value_parser!(u32).range(1..)),
+        )
+        .arg(
+            Arg::new("interval")
+                .long("interval")
+                .help("length of the time series and progress intervals, such as 500ms or 5s")
+                .default_value("1s")
+                .value_parser(|interval: &str| match parse_duration(interval)? {
+                    interval if interval < Duration::from_millis(10) => {
+                        Err("interval must be at least 10ms".to_string())
+                    }
+                    interval => Ok(interval),
+                }),
+        )
+        .arg(
+            Arg::new("timeout")
+                .long("timeout")
+                .help("time to wait for a response before replacing the connection, such as 2s")
+                .value_parser(|timeout: &str| match parse_duration(timeout)? {
+                    timeout if timeout.is_zero() => Err("timeout must be positive".to_string()),
+                    timeout => Ok(timeout),
+                }),
+        )
+        .arg(
+            Arg::new("stages")
+                .long("stages")
+                .help("load profile to follow instead of -d and -R, such as \"30s@0->100rps, 2m@100rps\" or \"1m@200c, 1m@400c\", can not be combined with --warmup, use a first stage to warm up")
+                .conflicts_with_all(["duration", "rate", "warmup"])
+                .value_parser(StagePlan::parse),
+        )
+        .arg(
+            Arg::new("warmup")
+                .long("warmup")
+                .help("generate load for this long before measuring, such as 10s")
+                .default_value("0s")
+                .value_parser(parse_duration),
+        )
+        .arg(
+            Arg::new("show-warmup")
+                .long("show-warmup")
+                .help("print statistics of the warmup period separately")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("no-keepalive")
+                .long("no-keepalive")
+                .help("send Connection: close and open a new connection for every request")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("max-requests-per-conn")
+                .long("max-requests-per-conn")
+                .help("replace a connection after this many requests")
+                .conflicts_with("no-keepalive")
+                .value_parser(value_parser!(u64).range(1..)),
+        )
+        .arg(
+            Arg::new("pipeline")
+                .long("pipeline")
+                .help("how many requests a connection may have waiting for a response")
+                .default_value("1")
+                .value_parser(value_parser!(u16).range(1..)),
+        )
+        .arg(
+            Arg::new("method")
+                .short('X')
+                .help("request method to use")
+                .default_value("GET")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("data")
+                .long("data")
+                .help("request body to send")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("data-file")
+                .long("data-file")
+                .help("file with request body to send, line breaks are stripped")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("data-binary")
+                .long("data-binary")
+                .help("file with request body to send as is")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("csv")
+                .long("csv")
+                .help("CSV file with a header row to take {{csv:<column>}} values from")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("scenario")
+                .long("scenario")
+                .help("JSON file with weighted endpoints to request instead of the url path")
+                .conflicts_with_all(["method", "body"])
+                .value_parser(value_parser!(String)),
+        )
+        .group(ArgGroup::new("body").args(["data", "data-file", "data-binary"]))
+        .arg(
+            Arg::new("sni")
+                .long("sni")
+                .help("server name to send in TLS SNI instead of the url host")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("cacert")
+                .long("cacert")
+                .help("PEM file with CA certificates to verify the server with")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("cert")
+                .long("cert")
+                .help("PEM file with client certificate chain")
+                .requires("key")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("key")
+                .long("key")
+                .help("PEM file with client private key")
+                .requires("cert")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("insecure")
+                .short('k')
+                .long("insecure")
+                .help("skip TLS certificate verification")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .help("format of the results printed to stdout")
+                .default_value("text")
+                .value_parser(["text", "json"]),
+        )
+        .arg(
+            Arg::new("output-file")
+                .long("output-file")
+                .help("file to write JSON results to")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("timeseries-file")
+                .long("timeseries-file")
+                .help("file to write CSV time series to")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("junit-file")
+                .long("junit-file")
+                .help("file to write JUnit XML with a test case per threshold to")
+                .value_parser(value_parser!(String)),
+        )
+        .arg(
+            Arg::new("threshold")
+                .long("threshold")
+                .help("assertion on the results such as p99<250ms, error_rate<0.1% or rps>5000, can be repeated")
+                .action(ArgAction::Append)
+                .value_parser(|threshold: &str| {
+                    Threshold::parse(threshold).map(|_| threshold.to_string())
+                }),
+        )
         .arg(Arg::new("url").required(true))
-        .get_matches();
-    return parsed_args;
+        .get_matches()
 }
 
-pub fn get_parsed_args() -> CliArgs {
+pub fn get_parsed_args() -> Result<CliArgs, String> {
     let argmatches = parse_cli_arguments();
     let threads = argmatches.get_one::<u8>("threads").unwrap();
     let connections = argmatches.get_one::<usize>("conns").unwrap();
-    let duration = argmatches.get_one::<usize>("duration").unwrap();
-    let set_header: Option<String> = match argmatches.get_one::<String>("header") {
-        Some(header) => Some(header.clone()),
-        None => None,
+    let stages = argmatches.get_one::<StagePlan>("stages").cloned();
+    let duration = match &stages {
+        Some(stages) => Some(stages.duration().as_secs_f64().ceil() as usize),
+        None => argmatches.get_one::<usize>("duration").copied(),
+    };
+    let headers: Vec<String> = argmatches
+        .get_many::<String>("header")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    let rate = argmatches.get_one::<u32>("rate").copied();
+    let tls_options = TlsOptions {
+        sni: argmatches.get_one::<String>("sni").cloned(),
+        ca_file: argmatches.get_one::<String>("cacert").cloned(),
+        cert_file: argmatches.get_one::<String>("cert").cloned(),
+        key_file: argmatches.get_one::<String>("key").cloned(),
+        insecure: argmatches.get_flag("insecure"),
     };
+    let method = argmatches.get_one::<String>("method").unwrap();
+    let body = read_body(&argmatches)?;
     let url = argmatches
         .get_one::<String>("url")
         .expect("unable to parse url");
 
-    return CliArgs::new(
-        threads.clone(),
-        set_header,
-        connections.clone(),
-        duration.clone(),
-        url.clone(),
-    );
+    Ok(CliArgs {
+        threads: *threads,
+        headers,
+        default_headers: !argmatches.get_flag("no-default-headers"),
+        connections: *connections,
+        duration,
+        requests: argmatches.get_one::<u64>("requests").copied(),
+        rate,
+        interval: *argmatches.get_one::<Duration>("interval").unwrap(),
+        warmup: *argmatches.get_one::<Duration>("warmup").unwrap(),
+        stages,
+        timeout: argmatches.get_one::<Duration>("timeout").copied(),
+        show_warmup: argmatches.get_flag("show-warmup"),
+        keep_alive: !argmatches.get_flag("no-keepalive"),
+        max_requests_per_conn: match argmatches.get_flag("no-keepalive") {
+            true => Some(1),
+            false => argmatches.get_one::<u64>("max-requests-per-conn").copied(),
+        },
+        pipeline: *argmatches.get_one::<u16>("pipeline").unwrap() as usize,
+        tls_options,
+        method: method.clone(),
+        body,
+        binary_body: argmatches.contains_id("data-binary"),
+        csv_file: argmatches.get_one::<String>("csv").cloned(),
+        scenario_file: argmatches.get_one::<String>("scenario").cloned(),
+        output_format: match argmatches.get_one::<String>("output").unwrap().as_str() {
+            "json" => OutputFormat::Json,
+            _ => OutputFormat::Text,
+        },
+        output_file: argmatches.get_one::<String>("output-file").cloned(),
+        timeseries_file: argmatches.get_one::<String>("timeseries-file").cloned(),
+        junit_file: argmatches.get_one::<String>("junit-file").cloned(),
+        thresholds: argmatches
+            .get_many::<String>("threshold")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+        url: url.clone(),
+    })
+}
+
+fn read_body(argmatches: &ArgMatches) -> Result<Option<Vec<u8>>, String> {
+    if let Some(data) = argmatches.get_one::<String>("data") {
+        return Ok(Some(data.as_bytes().to_vec()));
+    }
+    if let Some(path) = argmatches.get_one::<String>("data-file") {
+        let data = fs::read_to_string(path)
+            .map_err(|e| format!("unable to read data file {path}: {e}"))?;
+        return Ok(Some(data.replace(['\r', '\n'], "").into_bytes()));
+    }
+    argmatches
+        .get_one::<String>("data-binary")
+        .map(|path| fs::read(path).map_err(|e| format!("unable to read data file {path}: {e}")))
+        .transpose()
 }
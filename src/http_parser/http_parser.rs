@@ -1,120 +1,287 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Default)]
 pub enum ParserState {
+    #[default]
     Started,
     Version,
-    StatusFirstChar,
-    Status,
+    StatusCode,
+    Reason,
     HeaderStart,
     HeaderValue,
-    HeaderEnd,
     Body,
-    End,
+    ChunkSize,
+    ChunkExtension,
+    ChunkData,
+    ChunkDataEnd,
+    UntilClose,
+    Invalid,
 }
 
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct ParsedResponse {
+    pub status_code: u16,
+}
+
+#[derive(Default)]
 pub struct HTTParser {
     pub state: ParserState,
     version: Vec<u8>,
-    pub status_code_first_char: char,
+    pub status_code: u16,
+    pub reason: String,
+    status_code_digits: u8,
     pub responses_parsed: usize,
     pub headers: HashMap<String, String>,
+    current_header: String,
+    current_header_value: String,
+    body_remaining: usize,
+    in_trailers: bool,
+    head_requests: VecDeque<bool>,
 }
 
 impl HTTParser {
     pub fn new() -> HTTParser {
-        return HTTParser {
+        HTTParser {
             state: ParserState::Started,
             version: vec![],
-            status_code_first_char: '0',
+            status_code: 0,
+            reason: String::new(),
+            status_code_digits: 0,
             responses_parsed: 0,
             headers: HashMap::new(),
+            current_header: String::new(),
+            current_header_value: String::new(),
+            body_remaining: 0,
+            in_trailers: false,
+            head_requests: VecDeque::new(),
+        }
+    }
+
+    pub fn expect_response(&mut self, head_request: bool) {
+        self.head_requests.push_back(head_request);
+    }
+
+    // the last expected response will not arrive because its request was never sent
+    pub fn forget_expected_response(&mut self) {
+        self.head_requests.pop_back();
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.state == ParserState::Started && self.head_requests.is_empty()
+    }
+
+    pub fn finish(&mut self) -> Option<ParsedResponse> {
+        match self.state {
+            ParserState::UntilClose => {
+                let mut completed = vec![];
+                self.finish_response(&mut completed);
+                completed.pop()
+            }
+            _ => None,
+        }
+    }
+
+    fn start_response(&mut self) {
+        self.version.clear();
+        self.headers.clear();
+        self.current_header.clear();
+        self.current_header_value.clear();
+        self.status_code = 0;
+        self.reason.clear();
+        self.status_code_digits = 0;
+        self.body_remaining = 0;
+        self.in_trailers = false;
+        self.state = ParserState::Version;
+    }
+
+    fn finish_response(&mut self, completed: &mut Vec<ParsedResponse>) {
+        self.state = ParserState::Started;
+        if (100..200).contains(&self.status_code) {
+            return;
+        }
+        self.head_requests.pop_front();
+        self.responses_parsed += 1;
+        completed.push(ParsedResponse {
+            status_code: self.status_code,
+        });
+    }
+
+    fn has_no_body(&self) -> bool {
+        let head_request = self.head_requests.front().copied().unwrap_or(false);
+        head_request
+            || (100..200).contains(&self.status_code)
+            || self.status_code == 204
+            || self.status_code == 304
+    }
+
+    fn finish_headers(&mut self, completed: &mut Vec<ParsedResponse>) {
+        if self.in_trailers {
+            self.finish_response(completed);
+            return;
+        }
+        if self.has_no_body() {
+            self.finish_response(completed);
+            return;
+        }
+        if let Some(transfer_encoding) = self.headers.get("transfer-encoding") {
+            let last_coding = transfer_encoding.rsplit(',').next().unwrap_or("");
+            match last_coding.trim().eq_ignore_ascii_case("chunked") {
+                true => {
+                    self.body_remaining = 0;
+                    self.state = ParserState::ChunkSize;
+                }
+                false => self.state = ParserState::UntilClose,
+            }
+            return;
+        }
+        let content_length = match self.headers.get("content-length") {
+            None => {
+                self.state = ParserState::UntilClose;
+                return;
+            }
+            Some(value) => value.parse::<usize>().ok(),
         };
+        match content_length {
+            None => self.state = ParserState::Invalid,
+            Some(0) => self.finish_response(completed),
+            Some(length) => {
+                self.body_remaining = length;
+                self.state = ParserState::Body;
+            }
+        }
     }
 
-    pub fn parse(&mut self, data: &[u8]) {
-        let mut current_header = String::new();
-        let mut current_header_value = String::new();
-        let mut body_size = 0;
-        for byte in data {
+    fn finish_chunk_size(&mut self) {
+        match self.body_remaining {
+            0 => {
+                self.in_trailers = true;
+                self.state = ParserState::HeaderStart;
+            }
+            _ => self.state = ParserState::ChunkData,
+        }
+    }
+
+    pub fn parse(&mut self, data: &[u8]) -> Vec<ParsedResponse> {
+        let mut completed = vec![];
+        let mut position = 0;
+        while position < data.len() {
+            let byte = data[position];
+            position += 1;
             match self.state {
                 ParserState::Started => {
-                    if *byte == 47 {
+                    if byte == 47 {
                         // "/"
-                        self.state = ParserState::Version
+                        self.start_response();
                     }
                 }
                 ParserState::Version => {
-                    if *byte == 32 {
+                    if byte == 32 {
                         // space
-                        self.state = ParserState::StatusFirstChar
+                        self.state = ParserState::StatusCode
                     } else {
-                        self.version.push(*byte);
+                        self.version.push(byte);
                     }
                 }
-                ParserState::StatusFirstChar => {
-                    self.status_code_first_char = *byte as char;
-                    self.state = ParserState::Status;
+                ParserState::StatusCode => {
+                    if byte.is_ascii_digit() && self.status_code_digits < 3 {
+                        self.status_code = self.status_code * 10 + (byte - b'0') as u16;
+                        self.status_code_digits += 1;
+                    } else if self.status_code_digits < 3 {
+                        self.state = ParserState::Invalid;
+                    } else if byte == 32 || byte == 13 {
+                        // space or \r
+                        self.state = ParserState::Reason;
+                    } else if byte == 10 {
+                        self.state = ParserState::HeaderStart;
+                    } else {
+                        self.state = ParserState::Invalid;
+                    }
                 }
-                ParserState::Status => {
-                    if *byte == 10 {
+                ParserState::Reason => {
+                    if byte == 10 {
                         // '\n'
+                        let reason_length = self.reason.trim_end().len();
+                        self.reason.truncate(reason_length);
                         self.state = ParserState::HeaderStart
-                    } else {
-                        continue;
+                    } else if byte != 13 {
+                        self.reason.push(byte as char);
                     }
                 }
                 ParserState::HeaderStart => {
-                    if *byte == 32 || *byte == 13 {
-                        // space or \r
+                    if byte == 13 {
+                        // \r
                         continue;
-                    } else if *byte == 10 {
-                        body_size = self
-                            .headers
-                            .get(&"content-length".to_string())
-                            .unwrap_or(&"1".to_string())
-                            .parse::<i32>()
-                            .unwrap()
-                            - 1;
-                        match body_size {
-                            0 => self.state = ParserState::Started,
-                            _ => self.state = ParserState::Body,
-                        }
-                    } else if *byte == 58 {
+                    } else if byte == 10 {
+                        self.finish_headers(&mut completed);
+                    } else if byte == 58 {
                         // :
                         self.state = ParserState::HeaderValue;
                     } else {
-                        current_header.push(*byte as char);
+                        self.current_header.push(byte as char);
                     }
                 }
                 ParserState::HeaderValue => {
-                    if *byte == 32 || *byte == 13 {
-                        // space or \r
-                        continue;
-                    } else if *byte == 10 {
+                    if byte == 10 {
                         // \n
                         self.headers.insert(
-                            current_header.to_lowercase().clone(),
-                            current_header_value.clone(),
+                            self.current_header.trim().to_lowercase(),
+                            self.current_header_value.trim().to_string(),
                         );
-                        current_header.clear();
-                        current_header_value.clear();
+                        self.current_header.clear();
+                        self.current_header_value.clear();
                         self.state = ParserState::HeaderStart;
                     } else {
-                        current_header_value.push(*byte as char);
+                        self.current_header_value.push(byte as char);
+                    }
+                }
+                ParserState::Body | ParserState::ChunkData => {
+                    let available = data.len() - position + 1;
+                    let consumed = available.min(self.body_remaining);
+                    position += consumed - 1;
+                    self.body_remaining -= consumed;
+                    if self.body_remaining > 0 {
+                        continue;
+                    }
+                    match self.state {
+                        ParserState::Body => self.finish_response(&mut completed),
+                        _ => self.state = ParserState::ChunkDataEnd,
                     }
                 }
-                ParserState::Body => {
-                    if body_size == 0 {
-                        self.responses_parsed += 1;
-                        self.state = ParserState::Started;
+                ParserState::ChunkSize => {
+                    let digit = match byte {
+                        b'0'..=b'9' => Some(byte - b'0'),
+                        b'a'..=b'f' => Some(byte - b'a' + 10),
+                        b'A'..=b'F' => Some(byte - b'A' + 10),
+                        _ => None,
+                    };
+                    match (digit, byte) {
+                        (Some(digit), _) => {
+                            match self.body_remaining.checked_mul(16) {
+                                Some(size) => self.body_remaining = size + digit as usize,
+                                None => self.state = ParserState::Invalid,
+                            };
+                        }
+                        (None, b';') => self.state = ParserState::ChunkExtension,
+                        (None, b'\r' | b' ' | b'\t') => {}
+                        (None, b'\n') => self.finish_chunk_size(),
+                        (None, _) => self.state = ParserState::Invalid,
                     }
-                    body_size -= 1;
                 }
-
-                _ => {}
+                ParserState::ChunkExtension => {
+                    if byte == 10 {
+                        self.finish_chunk_size();
+                    }
+                }
+                ParserState::ChunkDataEnd => match byte {
+                    b'\r' => {}
+                    b'\n' => self.state = ParserState::ChunkSize,
+                    _ => self.state = ParserState::Invalid,
+                },
+                ParserState::UntilClose => break,
+                ParserState::Invalid => break,
             }
         }
+        completed
     }
 }
 
@@ -124,7 +291,7 @@ mod test_parser {
 
     use crate::http_parser::http_parser::ParserState;
 
-    use super::HTTParser;
+    use super::{HTTParser, ParsedResponse};
 
     #[test]
     fn test_parsing_response() {
@@ -135,7 +302,7 @@ mod test_parser {
         headers_hashmap.insert("content-length".to_string(), "11".to_string());
         headers_hashmap.insert("content-type".to_string(), "plaintext".to_string());
         parser.parse(response);
-        assert_eq!(parser.status_code_first_char, '2');
+        assert_eq!(parser.status_code, 200);
         assert_eq!(parser.headers, headers_hashmap);
     }
 
@@ -166,18 +333,221 @@ mod test_parser {
     }
 
     #[test]
-    fn test_parser_status_code_first_char() {
-        let response = b"HTTP/1.1 301\r\n\r\n";
+    fn test_parser_status_code() {
+        let response = b"HTTP/1.1 304\r\n\r\n";
         let mut parser = HTTParser::new();
         parser.parse(response);
-        assert_eq!(parser.status_code_first_char, '3');
+        assert_eq!(parser.status_code, 304);
+        assert_eq!(parser.reason, "");
         assert_eq!(parser.state, ParserState::Started);
     }
     #[test]
     fn test_parser_state_with_empty_body() {
-        let response = b"HTTP/1.1 301\r\n\r\n";
+        let response = b"HTTP/1.1 204\r\n\r\n";
         let mut parser = HTTParser::new();
         parser.parse(response);
         assert_eq!(parser.state, ParserState::Started);
     }
+
+    const PIPELINED_RESPONSES: &[u8] =
+        b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\nX-First: yes\r\n\r\nHello world\
+HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n\
+HTTP/1.1 301 Moved Permanently\r\nLocation: /next\r\ncontent-length: 5\r\n\r\nmoved";
+
+    fn parse_in_chunks(
+        data: &[u8],
+        chunk_sizes: &mut dyn Iterator<Item = usize>,
+    ) -> (HTTParser, Vec<ParsedResponse>) {
+        let mut parser = HTTParser::new();
+        let mut completed = vec![];
+        let mut position = 0;
+        while position < data.len() {
+            let end = (position + chunk_sizes.next().unwrap().max(1)).min(data.len());
+            completed.extend(parser.parse(&data[position..end]));
+            position = end;
+        }
+        (parser, completed)
+    }
+
+    #[test]
+    fn test_pipelined_responses_in_one_buffer() {
+        let mut parser = HTTParser::new();
+        let completed = parser.parse(PIPELINED_RESPONSES);
+        let status_codes: Vec<u16> = completed
+            .iter()
+            .map(|response| response.status_code)
+            .collect();
+        assert_eq!(status_codes, vec![200, 404, 301]);
+        assert_eq!(parser.reason, "Moved Permanently");
+        assert_eq!(parser.responses_parsed, 3);
+        assert_eq!(parser.state, ParserState::Started);
+    }
+
+    #[test]
+    fn test_headers_cleared_between_responses() {
+        let mut parser = HTTParser::new();
+        parser.parse(PIPELINED_RESPONSES);
+        let mut last_headers: HashMap<String, String> = HashMap::new();
+        last_headers.insert("location".to_string(), "/next".to_string());
+        last_headers.insert("content-length".to_string(), "5".to_string());
+        assert_eq!(parser.headers, last_headers);
+    }
+
+    #[test]
+    fn test_split_at_every_offset() {
+        let mut whole = HTTParser::new();
+        let expected = whole.parse(PIPELINED_RESPONSES);
+        for offset in 0..=PIPELINED_RESPONSES.len() {
+            let mut parser = HTTParser::new();
+            let mut completed = parser.parse(&PIPELINED_RESPONSES[..offset]);
+            completed.extend(parser.parse(&PIPELINED_RESPONSES[offset..]));
+            assert_eq!(completed, expected, "split at offset {offset}");
+            assert_eq!(parser.headers, whole.headers, "split at offset {offset}");
+            assert_eq!(
+                parser.state,
+                ParserState::Started,
+                "split at offset {offset}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_byte_by_byte_parsing() {
+        let mut whole = HTTParser::new();
+        let expected = whole.parse(PIPELINED_RESPONSES);
+        let (parser, completed) = parse_in_chunks(PIPELINED_RESPONSES, &mut std::iter::repeat(1));
+        assert_eq!(completed, expected);
+        assert_eq!(parser.headers, whole.headers);
+    }
+
+    #[test]
+    fn test_random_chunk_sizes() {
+        let stream = PIPELINED_RESPONSES.repeat(20);
+        let mut whole = HTTParser::new();
+        let expected = whole.parse(&stream);
+        assert_eq!(expected.len(), 60);
+        let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+        for _ in 0..200 {
+            let mut chunk_sizes = std::iter::from_fn(|| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                Some((seed % 97) as usize)
+            });
+            let (parser, completed) = parse_in_chunks(&stream, &mut chunk_sizes);
+            assert_eq!(completed, expected);
+            assert_eq!(parser.responses_parsed, 60);
+        }
+    }
+
+    #[test]
+    fn test_invalid_content_length() {
+        let response = b"HTTP/1.1 200 OK\r\nContent-Length: eleven\r\n\r\nHello world";
+        let mut parser = HTTParser::new();
+        assert!(parser.parse(response).is_empty());
+        assert_eq!(parser.state, ParserState::Invalid);
+    }
+
+    const CHUNKED_RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+5;name=value\r\nHello\r\n6\r\n world\r\n0\r\nX-Checksum: abc\r\n\r\n\
+HTTP/1.1 201 Created\r\nContent-Length: 2\r\n\r\nok";
+
+    #[test]
+    fn test_chunked_body_with_trailers() {
+        let second_response = CHUNKED_RESPONSE
+            .windows(12)
+            .position(|window| window == b"HTTP/1.1 201")
+            .unwrap();
+        let mut parser = HTTParser::new();
+        let completed = parser.parse(&CHUNKED_RESPONSE[..second_response]);
+        assert_eq!(completed.len(), 1);
+        assert_eq!(parser.headers.get("x-checksum"), Some(&"abc".to_string()));
+        let completed = parser.parse(&CHUNKED_RESPONSE[second_response..]);
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].status_code, 201);
+        assert_eq!(parser.state, ParserState::Started);
+    }
+
+    #[test]
+    fn test_chunked_split_at_every_offset() {
+        for offset in 0..=CHUNKED_RESPONSE.len() {
+            let mut parser = HTTParser::new();
+            let mut completed = parser.parse(&CHUNKED_RESPONSE[..offset]);
+            completed.extend(parser.parse(&CHUNKED_RESPONSE[offset..]));
+            assert_eq!(completed.len(), 2, "split at offset {offset}");
+            assert_eq!(
+                parser.state,
+                ParserState::Started,
+                "split at offset {offset}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_chunk_size() {
+        let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n";
+        let mut parser = HTTParser::new();
+        assert!(parser.parse(response).is_empty());
+        assert_eq!(parser.state, ParserState::Invalid);
+    }
+
+    #[test]
+    fn test_body_delimited_by_connection_close() {
+        let response = b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\nstreamed until the end";
+        let mut parser = HTTParser::new();
+        assert!(parser.parse(response).is_empty());
+        assert_eq!(parser.state, ParserState::UntilClose);
+        let response = parser.finish().unwrap();
+        assert_eq!(response.status_code, 200);
+        assert_eq!(parser.responses_parsed, 1);
+        assert_eq!(parser.finish(), None);
+    }
+
+    #[test]
+    fn test_head_response_has_no_body() {
+        let responses = b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\n\
+HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nHello world";
+        let mut parser = HTTParser::new();
+        parser.expect_response(true);
+        parser.expect_response(false);
+        assert_eq!(parser.parse(responses).len(), 2);
+        assert_eq!(parser.state, ParserState::Started);
+    }
+
+    #[test]
+    fn test_no_body_status_codes() {
+        let responses = b"HTTP/1.1 204 No Content\r\nContent-Length: 5\r\n\r\n\
+HTTP/1.1 304 Not Modified\r\nTransfer-Encoding: chunked\r\n\r\n";
+        let mut parser = HTTParser::new();
+        assert_eq!(parser.parse(responses).len(), 2);
+        assert_eq!(parser.state, ParserState::Started);
+    }
+
+    #[test]
+    fn test_interim_response_is_skipped() {
+        let responses = b"HTTP/1.1 100 Continue\r\n\r\n\
+HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+        let mut parser = HTTParser::new();
+        let completed = parser.parse(responses);
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].status_code, 200);
+        assert_eq!(parser.responses_parsed, 1);
+    }
+
+    #[test]
+    fn test_reason_phrase() {
+        let response = b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n";
+        let mut parser = HTTParser::new();
+        let completed = parser.parse(response);
+        assert_eq!(completed, vec![ParsedResponse { status_code: 503 }]);
+        assert_eq!(parser.reason, "Service Unavailable");
+    }
+
+    #[test]
+    fn test_malformed_status_code() {
+        let response = b"HTTP/1.1 20 OK\r\nContent-Length: 0\r\n\r\n";
+        let mut parser = HTTParser::new();
+        assert!(parser.parse(response).is_empty());
+        assert_eq!(parser.state, ParserState::Invalid);
+    }
 }
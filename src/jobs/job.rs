@@ -1,17 +1,17 @@
 use std::sync::mpsc::Sender;
 
-use crate::statistics::stats::WorkerStats;
+use crate::statistics::stats::WorkerMessage;
 
 pub trait Job: CloneJob {
-    fn execute(&mut self, stats_sender: Sender<WorkerStats>);
+    fn execute(&mut self, worker_index: usize, stats_sender: Sender<WorkerMessage>);
 }
 
 pub trait CloneJob {
-    fn clone_job<'a>(&self) -> Box<dyn Job + Send + Sync>;
+    fn clone_job(&self) -> Box<dyn Job + Send + Sync>;
 }
 
 impl Clone for Box<dyn Job> {
     fn clone(&self) -> Self {
-        return self.clone_job();
+        self.clone_job()
     }
 }
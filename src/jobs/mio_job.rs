@@ -1,126 +1,886 @@
 use std::{
     io::{ErrorKind, Read, Write},
-    net::{SocketAddr, ToSocketAddrs},
-    time::Instant,
+    mem,
+    net::SocketAddr,
+    time::{Duration, Instant},
 };
 
-use mio::{net::TcpStream, Events, Interest, Poll, Token};
+use mio::{event::Event, net::TcpStream, Events, Interest, Poll, Token};
+use rustls::ClientConnection;
 use slab::Slab;
 
 use super::job::{CloneJob, Job};
-use crate::http_parser::http_parser::{HTTParser, ParserState};
-use crate::{statistics::stats::WorkerStats, url_parser::ParsedUrlHeader};
+use super::pipeline::{InFlightRequest, MatchedResponse, Pipeline};
+use crate::http_parser::http_parser::ParsedResponse;
+use crate::request_generator::generator::RequestGenerator;
+use crate::stages::{StagePlan, StageUnit};
+use crate::statistics::stats::WorkerStats;
+use crate::statistics::stats::{
+    interval_at, EndpointStats, ProgressSnapshot, RequestError, StageStats, WorkerMessage,
+};
+use crate::tls::TlsSettings;
+use crate::utils::split_evenly;
+
+// how often the connection count is adjusted while following a connection ramp
+const CONNECTION_RAMP_TICK: Duration = Duration::from_millis(10);
+// a failed connect is retried after a delay that doubles with every failure in a row
+const FIRST_RECONNECT_DELAY: Duration = Duration::from_millis(10);
+const LONGEST_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+// a run bounded only by -n gives up once every connection failed this many connects in a row
+const MAX_FAILED_CONNECTS: u32 = 10;
 
 enum HTTPReadREsult {
-    Complete(usize, char),
-    Partial,
+    Complete(usize, Vec<MatchedResponse>),
+    Partial(usize),
     Blocked,
-    Error,
+    Error(RequestError),
+}
+
+// kind of the failure and the endpoint of the request that could not be sent
+type SendError = (RequestError, Option<usize>);
+
+#[derive(Clone, Copy)]
+struct ConnectionLimits {
+    max_requests: Option<u64>,
+    pipeline_depth: usize,
 }
 
 struct HTTPConnection {
-    tcp_stream: TcpStream,
-    parser: HTTParser,
-    request_sent_time: Option<Instant>,
+    tcp_stream: Option<TcpStream>,
+    tls: Option<ClientConnection>,
+    pipeline: Pipeline,
+    write_buffer: Vec<u8>,
+    write_offset: usize,
+    next_send_time: Option<Instant>,
+    requests_left: Option<u64>,
+    requests_sent: u64,
+    limits: ConnectionLimits,
+    connected: bool,
+    handshake_start_time: Option<Instant>,
+    handshake_recorded: bool,
+    connect_start_time: Option<Instant>,
+    failed_connects: u32,
+    reconnect_time: Option<Instant>,
+}
+
+fn reconnect_delay(failed_connects: u32) -> Duration {
+    FIRST_RECONNECT_DELAY
+        .saturating_mul(1 << failed_connects.saturating_sub(1).min(16))
+        .min(LONGEST_RECONNECT_DELAY)
+}
+
+fn flush_tls(tls: &mut ClientConnection, tcp_stream: &mut TcpStream) -> std::io::Result<()> {
+    while tls.wants_write() {
+        match tls.write_tls(tcp_stream) {
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+fn write_to_transport(
+    tcp_stream: &mut TcpStream,
+    tls: Option<&mut ClientConnection>,
+    data: &[u8],
+) -> std::io::Result<usize> {
+    let Some(tls) = tls else {
+        return tcp_stream.write(data);
+    };
+    loop {
+        let written = tls.writer().write(data)?;
+        flush_tls(tls, tcp_stream)?;
+        if written > 0 {
+            return Ok(written);
+        }
+        if tls.wants_write() {
+            return Err(ErrorKind::WouldBlock.into());
+        }
+    }
 }
 
 impl HTTPConnection {
-    fn new(tcp_address: std::net::SocketAddr) -> HTTPConnection {
-        let new_stream = TcpStream::connect(tcp_address)
-            .expect("unable to establish tcp connection. check if the server is available");
-        return HTTPConnection {
-            tcp_stream: new_stream,
-            parser: HTTParser::new(),
-            request_sent_time: None,
+    fn new(limits: ConnectionLimits) -> HTTPConnection {
+        HTTPConnection {
+            tcp_stream: None,
+            tls: None,
+            pipeline: Pipeline::default(),
+            write_buffer: vec![],
+            write_offset: 0,
+            next_send_time: None,
+            requests_left: None,
+            requests_sent: 0,
+            limits,
+            connected: false,
+            handshake_start_time: None,
+            handshake_recorded: false,
+            connect_start_time: None,
+            failed_connects: 0,
+            reconnect_time: None,
+        }
+    }
+
+    fn connect(
+        &mut self,
+        socket_addr: SocketAddr,
+        tls: Option<&TlsSettings>,
+        token: Token,
+        poll: &mut Poll,
+    ) -> std::io::Result<()> {
+        self.tls = tls
+            .map(|settings| {
+                ClientConnection::new(settings.config.clone(), settings.server_name.clone())
+            })
+            .transpose()
+            .map_err(std::io::Error::other)?;
+        let mut tcp_stream = TcpStream::connect(socket_addr)?;
+        poll.registry().register(
+            &mut tcp_stream,
+            token,
+            Interest::WRITABLE | Interest::READABLE,
+        )?;
+        self.tcp_stream = Some(tcp_stream);
+        self.connect_start_time = Some(Instant::now());
+        self.reconnect_time = None;
+        Ok(())
+    }
+
+    fn schedule_reconnect(&mut self, now: Instant) {
+        self.failed_connects += 1;
+        self.reconnect_time = Some(now + reconnect_delay(self.failed_connects));
+    }
+
+    fn read_from_transport(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        let Some(tcp_stream) = self.tcp_stream.as_mut() else {
+            return Err(ErrorKind::NotConnected.into());
+        };
+        let Some(tls) = self.tls.as_mut() else {
+            return tcp_stream.read(buffer);
         };
+        loop {
+            match tls.reader().read(buffer) {
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                result => return result,
+            }
+            if tls.read_tls(tcp_stream)? == 0 {
+                return Ok(0);
+            }
+            tls.process_new_packets().map_err(std::io::Error::other)?;
+            flush_tls(tls, tcp_stream)?;
+        }
+    }
+
+    fn flush_write_buffer(&mut self) -> std::io::Result<()> {
+        let Some(tcp_stream) = self.tcp_stream.as_mut() else {
+            return Err(ErrorKind::NotConnected.into());
+        };
+        while self.write_offset < self.write_buffer.len() {
+            let pending = &self.write_buffer[self.write_offset..];
+            match write_to_transport(tcp_stream, self.tls.as_mut(), pending) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(written) => self.write_offset += written,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+        self.write_buffer.clear();
+        self.write_offset = 0;
+        Ok(())
+    }
+
+    fn has_pending_writes(&self) -> bool {
+        self.write_offset < self.write_buffer.len()
     }
 
     fn read_available(&mut self) -> HTTPReadREsult {
         let mut buffer = [0; 4096];
-        match self.tcp_stream.read(&mut buffer) {
-            Err(e) => {
-                if e.kind() == ErrorKind::WouldBlock {
-                    HTTPReadREsult::Blocked
-                } else {
-                    HTTPReadREsult::Error
+        let mut received = 0;
+        let mut responses = vec![];
+        let mut closed = false;
+        loop {
+            match self.read_from_transport(&mut buffer) {
+                Ok(0) => {
+                    self.pipeline.close(Instant::now(), &mut responses);
+                    closed = true;
+                    break;
                 }
-            }
-            Ok(n) => {
-                self.parser.parse(&buffer[..n]);
-                match self.parser.state {
-                    ParserState::Body => HTTPReadREsult::Partial,
-                    ParserState::Started => {
-                        HTTPReadREsult::Complete(n, self.parser.status_code_first_char)
-                    }
-                    _ => HTTPReadREsult::Error,
+                Ok(n) => {
+                    received += n;
+                    self.pipeline
+                        .read(&buffer[..n], Instant::now(), &mut responses);
                 }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return HTTPReadREsult::Error(self.classify_error(&e)),
             }
         }
+        if self.pipeline.is_invalid() {
+            return HTTPReadREsult::Error(RequestError::ParseError);
+        }
+        if !responses.is_empty() {
+            return HTTPReadREsult::Complete(received, responses);
+        }
+        if closed && self.awaiting_response() && !self.pipeline.is_idle() {
+            return HTTPReadREsult::Error(RequestError::UnexpectedEof);
+        }
+        match received {
+            0 => HTTPReadREsult::Blocked,
+            received => HTTPReadREsult::Partial(received),
+        }
+    }
+
+    fn awaiting_response(&self) -> bool {
+        !self.pipeline.is_empty()
+    }
+
+    fn can_send(&self) -> bool {
+        self.pipeline.len() < self.limits.pipeline_depth
+            && self.has_requests_left()
+            && !self.is_exhausted()
     }
 
-    fn send_request(&mut self, request: &[u8]) {
-        match self.tcp_stream.write_all(request) {
-            Ok(_) => {
-                self.request_sent_time = Some(Instant::now());
+    fn classify_error(&self, error: &std::io::Error) -> RequestError {
+        match error.kind() {
+            ErrorKind::ConnectionRefused => RequestError::ConnectRefused,
+            ErrorKind::TimedOut if !self.connected => RequestError::ConnectTimeout,
+            ErrorKind::TimedOut => RequestError::ReadTimeout,
+            ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted => {
+                RequestError::ConnectionReset
             }
-            Err(e) => {
-                if e.kind() == std::io::ErrorKind::BrokenPipe {
-                } else {
-                    println!("{e}");
-                }
+            ErrorKind::BrokenPipe => RequestError::BrokenPipe,
+            ErrorKind::UnexpectedEof => RequestError::UnexpectedEof,
+            _ if error
+                .get_ref()
+                .is_some_and(|inner| inner.is::<rustls::Error>()) =>
+            {
+                RequestError::TlsError
+            }
+            _ => RequestError::Other,
+        }
+    }
+
+    fn on_writable(&mut self) -> std::io::Result<()> {
+        let Some(tcp_stream) = self.tcp_stream.as_mut() else {
+            return Err(ErrorKind::NotConnected.into());
+        };
+        if !self.connected {
+            if let Some(error) = tcp_stream.take_error()? {
+                return Err(error);
             }
+            self.connected = true;
+            self.handshake_start_time = Some(Instant::now());
+        }
+        if let Some(tls) = self.tls.as_mut() {
+            flush_tls(tls, tcp_stream)?;
+        }
+        if self.is_established() {
+            self.failed_connects = 0;
+        }
+        self.flush_write_buffer()
+    }
+
+    fn is_established(&self) -> bool {
+        self.connected && self.tls.as_ref().is_none_or(|tls| !tls.is_handshaking())
+    }
+
+    fn take_connect_time(&mut self) -> Option<Duration> {
+        if !self.connected {
+            return None;
+        }
+        self.connect_start_time
+            .take()
+            .map(|connect_start_time| connect_start_time.elapsed())
+    }
+
+    fn take_handshake_time(&mut self) -> Option<Duration> {
+        if self.tls.is_none() || self.handshake_recorded || !self.is_established() {
+            return None;
         }
+        self.handshake_recorded = true;
+        self.handshake_start_time
+            .map(|handshake_start_time| handshake_start_time.elapsed())
+    }
+
+    fn send_request(
+        &mut self,
+        request: &[u8],
+        endpoint: usize,
+        intended_send_time: Option<Instant>,
+    ) -> Result<(), SendError> {
+        self.write_buffer.extend_from_slice(request);
+        self.pipeline.push(
+            InFlightRequest {
+                sent_time: Instant::now(),
+                intended_send_time,
+                first_byte_time: None,
+                endpoint,
+            },
+            request.starts_with(b"HEAD "),
+        );
+        self.requests_sent += 1;
+        self.requests_left = self.requests_left.map(|left| left - 1);
+        self.flush_write_buffer().map_err(|e| {
+            // the request is counted as failed here, not again when the socket closes,
+            // and is sent again on the connection that replaces this one
+            let failed = self.pipeline.cancel_last();
+            self.write_buffer.clear();
+            self.write_offset = 0;
+            self.requests_sent -= 1;
+            self.requests_left = self.requests_left.map(|left| left + 1);
+            (
+                self.classify_error(&e),
+                failed.map(|request| request.endpoint),
+            )
+        })
+    }
+
+    fn has_requests_left(&self) -> bool {
+        self.requests_left != Some(0)
+    }
+
+    // a connection that sent its last request is replaced once the response arrives
+    fn is_exhausted(&self) -> bool {
+        self.limits
+            .max_requests
+            .is_some_and(|max_requests| self.requests_sent >= max_requests)
+    }
+
+    fn send_next_request(
+        &mut self,
+        generator: &mut Box<dyn RequestGenerator + Send + Sync>,
+        buffer: &mut Vec<u8>,
+        intended_send_time: Option<Instant>,
+    ) -> Result<(), SendError> {
+        if !self.can_send() {
+            return Ok(());
+        }
+        buffer.clear();
+        let endpoint = generator.next_request(buffer);
+        self.send_request(buffer, endpoint, intended_send_time)
+    }
+
+    // sends requests until the pipeline is full or the socket stops accepting data
+    fn fill_pipeline(
+        &mut self,
+        generator: &mut Box<dyn RequestGenerator + Send + Sync>,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SendError> {
+        while self.can_send() && !self.has_pending_writes() {
+            self.send_next_request(generator, buffer, None)?;
+        }
+        Ok(())
+    }
+
+    // endpoint of the oldest request waiting for its response
+    fn awaited_endpoint(&self) -> Option<usize> {
+        self.pipeline.oldest().map(|request| request.endpoint)
+    }
+
+    // the connect is timed until the socket is connected, the request until its response
+    fn timeout_start_time(&self) -> Option<Instant> {
+        match self.connected {
+            true => self.pipeline.oldest().map(|request| request.sent_time),
+            false => self
+                .connect_start_time
+                .filter(|_| self.tcp_stream.is_some()),
+        }
+    }
+
+    fn is_timed_out(&self, now: Instant, timeout: Duration) -> bool {
+        self.timeout_start_time()
+            .is_some_and(|start_time| now.saturating_duration_since(start_time) >= timeout)
+    }
+
+    fn is_due(&self, now: Instant) -> bool {
+        self.is_established()
+            && self.can_send()
+            && !self.has_pending_writes()
+            && self
+                .next_send_time
+                .is_some_and(|send_time| send_time <= now)
+    }
+
+    fn send_scheduled_request(
+        &mut self,
+        generator: &mut Box<dyn RequestGenerator + Send + Sync>,
+        buffer: &mut Vec<u8>,
+        next_send_time: Option<Instant>,
+    ) -> Result<(), SendError> {
+        let Some(intended_send_time) = self.next_send_time else {
+            return Ok(());
+        };
+        self.next_send_time = next_send_time;
+        self.send_next_request(generator, buffer, Some(intended_send_time))
     }
 }
 
-fn create_connection(socket_addr: SocketAddr) -> HTTPConnection {
-    let connection = HTTPConnection::new(socket_addr);
+fn open_connection(
+    socket_addr: SocketAddr,
+    tls: Option<&TlsSettings>,
+    token: Token,
+    pool: &mut Slab<HTTPConnection>,
+    poll: &mut Poll,
+) -> Result<(), RequestError> {
+    let connection = &mut pool[token.0];
     connection
+        .connect(socket_addr, tls, token, poll)
+        .map_err(|e| {
+            connection.tcp_stream = None;
+            connection.schedule_reconnect(Instant::now());
+            connection.classify_error(&e)
+        })
 }
 
+// returns the errors of the connects that failed right away
 fn fill_connection_slab(
     size: usize,
+    limits: ConnectionLimits,
     socket_addr: SocketAddr,
+    tls: Option<&TlsSettings>,
     pool: &mut Slab<HTTPConnection>,
     poll: &mut Poll,
-) {
+) -> Vec<RequestError> {
+    let mut errors = vec![];
     for _ in 0..size {
-        let new_connection = create_connection(socket_addr);
-        let token = pool.insert(new_connection);
-        poll.registry()
-            .register(
-                &mut pool[token].tcp_stream,
-                Token(token),
-                Interest::WRITABLE | Interest::READABLE,
-            )
-            .expect("cannot not register socket");
+        let token = Token(pool.insert(HTTPConnection::new(limits)));
+        if let Err(kind) = open_connection(socket_addr, tls, token, pool, poll) {
+            errors.push(kind);
+        }
     }
+    errors
 }
+
 fn reregister_socket_in_slab(
     socket_addr: SocketAddr,
+    tls: Option<&TlsSettings>,
     token: Token,
     pool: &mut Slab<HTTPConnection>,
     poll: &mut Poll,
+) -> Result<(), RequestError> {
+    let old_connection = &pool[token.0];
+    let mut connection = HTTPConnection::new(old_connection.limits);
+    connection.next_send_time = old_connection.next_send_time;
+    // a request lost with the connection is sent again on the new one
+    connection.requests_left = old_connection
+        .requests_left
+        .map(|left| left + old_connection.pipeline.len() as u64);
+    // a connection that failed before its TLS handshake finished counts as a failed connect
+    let connect_failed = !old_connection.is_established();
+    if connect_failed {
+        connection.failed_connects = old_connection.failed_connects;
+    }
+    if let Some(tcp_stream) = pool[token.0].tcp_stream.as_mut() {
+        poll.registry().deregister(tcp_stream).ok();
+    }
+    pool[token.0] = connection;
+    if connect_failed {
+        pool[token.0].schedule_reconnect(Instant::now());
+        return Ok(());
+    }
+    open_connection(socket_addr, tls, token, pool, poll)
+}
+
+// connections waiting out their reconnect delay are connected again once it passes
+fn reconnect_due_connections(
+    socket_addr: SocketAddr,
+    tls: Option<&TlsSettings>,
+    now: Instant,
+    pool: &mut Slab<HTTPConnection>,
+    poll: &mut Poll,
+) -> Vec<RequestError> {
+    let due: Vec<usize> = pool
+        .iter()
+        .filter(|(_, connection)| connection.reconnect_time.is_some_and(|time| time <= now))
+        .map(|(token, _)| token)
+        .collect();
+    due.into_iter()
+        .filter_map(|token| open_connection(socket_addr, tls, Token(token), pool, poll).err())
+        .collect()
+}
+
+fn time_until_next_reconnect(pool: &Slab<HTTPConnection>, now: Instant) -> Option<Duration> {
+    pool.iter()
+        .filter_map(|(_, connection)| connection.reconnect_time)
+        .min()
+        .map(|reconnect_time| reconnect_time.saturating_duration_since(now))
+}
+
+// returns the index of the first request left unscheduled
+fn schedule_connections(
+    start_time: Instant,
+    rate_plan: &StagePlan,
+    pool: &mut Slab<HTTPConnection>,
+) -> u64 {
+    for (index, (_, connection)) in pool.iter_mut().enumerate() {
+        connection.next_send_time = rate_plan
+            .request_time(index as u64)
+            .map(|offset| start_time + offset);
+    }
+    pool.len() as u64
+}
+
+fn assign_request_quotas(worker_requests: u64, pool: &mut Slab<HTTPConnection>) {
+    let shares = split_evenly(worker_requests, pool.len());
+    for ((_, connection), share) in pool.iter_mut().zip(shares) {
+        // a response awaited at this point is counted in the results as well
+        connection.requests_left = Some(share.saturating_sub(connection.pipeline.len() as u64));
+    }
+}
+
+fn time_until_next_timeout(
+    pool: &Slab<HTTPConnection>,
+    timeout: Duration,
+    now: Instant,
+) -> Option<Duration> {
+    pool.iter()
+        .filter_map(|(_, connection)| connection.timeout_start_time())
+        .min()
+        .map(|start_time| (start_time + timeout).saturating_duration_since(now))
+}
+
+// tokens of the connections over the timeout with the failure and the endpoint of the lost request
+fn find_timed_out(
+    pool: &Slab<HTTPConnection>,
+    timeout: Duration,
+    now: Instant,
+) -> Vec<(usize, RequestError, Option<usize>)> {
+    pool.iter()
+        .filter(|(_, connection)| connection.is_timed_out(now, timeout))
+        .map(|(token, connection)| {
+            let kind = match connection.connected {
+                true => RequestError::ReadTimeout,
+                false => RequestError::ConnectTimeout,
+            };
+            (token, kind, connection.awaited_endpoint())
+        })
+        .collect()
+}
+
+fn time_until_next_send(pool: &Slab<HTTPConnection>, now: Instant) -> Option<Duration> {
+    pool.iter()
+        .filter(|(_, connection)| connection.is_established() && connection.can_send())
+        .filter_map(|(_, connection)| connection.next_send_time)
+        .min()
+        .map(|send_time| send_time.saturating_duration_since(now))
+}
+
+// latencies are measured from the intended send time as well when a rate is set,
+// so a request sent late because of a slow server still counts its waiting time
+fn record_response(
+    stats: &mut WorkerStats,
+    response: &ParsedResponse,
+    request: &InFlightRequest,
+    response_time: Instant,
+    interval_index: usize,
+    stage_index: Option<usize>,
 ) {
-    pool[token.0] = create_connection(socket_addr);
-    poll.registry()
-        .register(
-            &mut pool[token.0].tcp_stream,
-            token,
-            Interest::WRITABLE | Interest::READABLE,
-        )
-        .expect("cannot register socket");
+    let bad_response = !(200..400).contains(&response.status_code);
+    let latency = response_time
+        .saturating_duration_since(request.sent_time)
+        .as_micros() as u64;
+    stats.latencies.record(latency);
+    let mut interval_latency = latency;
+    if let Some(first_byte_time) = request.first_byte_time {
+        let first_byte = first_byte_time.saturating_duration_since(request.sent_time);
+        let download = response_time.saturating_duration_since(first_byte_time);
+        stats
+            .first_byte_latencies
+            .record(first_byte.as_micros() as u64);
+        stats.download_latencies.record(download.as_micros() as u64);
+    }
+    if let Some(endpoint) = stats.endpoints.get_mut(request.endpoint) {
+        endpoint.request_count += 1;
+        endpoint.latencies.record(latency);
+        if bad_response {
+            endpoint.bad_requests += 1;
+        }
+    }
+    if let Some(intended_time) = request.intended_send_time {
+        let latency = response_time
+            .saturating_duration_since(intended_time)
+            .as_micros() as u64;
+        stats.corrected_latencies.record(latency);
+        interval_latency = latency;
+    }
+    let interval = interval_at(&mut stats.intervals, interval_index);
+    interval.latencies.record(interval_latency);
+    interval.request_count += 1;
+    if let Some(stage) = stage_index.and_then(|index| stats.stages.get_mut(index)) {
+        stage.request_count += 1;
+        stage.latencies.record(interval_latency);
+        if bad_response {
+            stage.bad_requests += 1;
+        }
+    }
+    if bad_response {
+        stats.bad_requests += 1;
+        interval.bad_requests += 1;
+    }
+    stats.request_count += 1;
+    *stats.status_codes.entry(response.status_code).or_insert(0) += 1;
+}
+
+// the connections of a worker, one that breaks is replaced by a new connection to the same address
+struct ConnectionPool {
+    slab: Slab<HTTPConnection>,
+    poll: Poll,
+    socket_addr: SocketAddr,
+    tls: Option<TlsSettings>,
+    limits: ConnectionLimits,
+    // idle connections over the target are closed while following a connection ramp
+    target: usize,
+    // requests are sent at the rate instead of whenever a connection can take one
+    paced: bool,
+    request: Vec<u8>,
+    // connects that failed are counted with the interval of the loop they failed in
+    connect_errors: Vec<RequestError>,
+}
+
+impl ConnectionPool {
+    fn fill(&mut self, size: usize) {
+        self.connect_errors.extend(fill_connection_slab(
+            size,
+            self.limits,
+            self.socket_addr,
+            self.tls.as_ref(),
+            &mut self.slab,
+            &mut self.poll,
+        ));
+    }
+
+    fn replace_connection(&mut self, token: Token) {
+        self.connect_errors.extend(
+            reregister_socket_in_slab(
+                self.socket_addr,
+                self.tls.as_ref(),
+                token,
+                &mut self.slab,
+                &mut self.poll,
+            )
+            .err(),
+        );
+    }
+
+    fn reconnect_due(&mut self, now: Instant) {
+        self.connect_errors.extend(reconnect_due_connections(
+            self.socket_addr,
+            self.tls.as_ref(),
+            now,
+            &mut self.slab,
+            &mut self.poll,
+        ));
+    }
+
+    fn follow_target(&mut self, target: usize) {
+        self.target = target;
+        if self.slab.len() < target {
+            self.fill(target - self.slab.len());
+        }
+        // connections waiting to reconnect are the first to go when over the target
+        let reconnecting: Vec<usize> = self
+            .slab
+            .iter()
+            .filter(|(_, connection)| connection.reconnect_time.is_some())
+            .map(|(token, _)| token)
+            .take(self.slab.len().saturating_sub(target))
+            .collect();
+        for token in reconnecting {
+            self.slab.remove(token);
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        event: &Event,
+        generator: &mut Box<dyn RequestGenerator + Send + Sync>,
+        stats: &mut WorkerStats,
+        interval_index: usize,
+        stage_index: Option<usize>,
+    ) {
+        let token = event.token();
+        let over_target = self.slab.len() > self.target;
+        let Some(connection) = self.slab.get_mut(token.0) else {
+            return;
+        };
+        // the socket of this event was already closed earlier in the batch
+        if connection.tcp_stream.is_none() {
+            return;
+        }
+        if event.is_readable() {
+            match connection.read_available() {
+                HTTPReadREsult::Complete(response_size, responses) => {
+                    // latencies end when the last byte was read, not when the event is handled
+                    for (response, request, response_time) in responses {
+                        record_response(
+                            stats,
+                            &response,
+                            &request,
+                            response_time,
+                            interval_index,
+                            stage_index,
+                        );
+                    }
+                    stats.received_data += response_size;
+                    interval_at(&mut stats.intervals, interval_index).received_data +=
+                        response_size;
+                }
+                HTTPReadREsult::Partial(response_size) => {
+                    stats.received_data += response_size;
+                    interval_at(&mut stats.intervals, interval_index).received_data +=
+                        response_size;
+                }
+                // a failed socket is replaced rather than written to again
+                HTTPReadREsult::Error(kind) => {
+                    let endpoint = connection.awaited_endpoint();
+                    stats.record_error(kind, interval_index, stage_index, endpoint);
+                    self.replace_connection(token);
+                    return;
+                }
+                _ => {}
+            }
+        }
+        if connection.is_exhausted() && !connection.awaiting_response() {
+            self.replace_connection(token);
+            return;
+        }
+        if event.is_writable() {
+            if let Err(error) = connection.on_writable() {
+                let kind = connection.classify_error(&error);
+                stats.record_error(kind, interval_index, stage_index, None);
+                self.replace_connection(token);
+                return;
+            }
+        }
+        if let Some(connect_time) = connection.take_connect_time() {
+            stats
+                .connect_latencies
+                .record(connect_time.as_micros() as u64);
+        }
+        if over_target && connection.is_established() && !connection.awaiting_response() {
+            if let Some(tcp_stream) = connection.tcp_stream.as_mut() {
+                self.poll.registry().deregister(tcp_stream).ok();
+            }
+            self.slab.remove(token.0);
+            return;
+        }
+        if let Some(handshake_time) = connection.take_handshake_time() {
+            stats
+                .handshake_latencies
+                .record(handshake_time.as_micros() as u64);
+        }
+        if !self.paced && connection.is_established() && !event.is_read_closed() {
+            if let Err((kind, endpoint)) = connection.fill_pipeline(generator, &mut self.request) {
+                stats.record_error(kind, interval_index, stage_index, endpoint);
+                self.replace_connection(token);
+                return;
+            }
+        }
+        if event.is_read_closed() || event.is_write_closed() {
+            self.replace_connection(token);
+        }
+    }
+
+    fn replace_timed_out(
+        &mut self,
+        timeout: Duration,
+        stats: &mut WorkerStats,
+        interval_index: usize,
+        stage_index: Option<usize>,
+    ) {
+        for (token, kind, endpoint) in find_timed_out(&self.slab, timeout, Instant::now()) {
+            // the response never arrived, so no latency is recorded for it
+            stats.record_error(kind, interval_index, stage_index, endpoint);
+            self.replace_connection(Token(token));
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct MioHTTPJob {
-    pub parsed_url: ParsedUrlHeader,
-    pub job_duration_sec: usize,
+    pub socket_address: SocketAddr,
+    pub request_generator: Box<dyn RequestGenerator + Send + Sync>,
+    pub endpoints: Vec<String>,
+    pub job_duration_sec: Option<usize>,
+    pub worker_requests: Option<Vec<u64>>,
     pub conn_quantity: usize,
+    pub request_rate: Option<f64>,
+    pub stats_interval: Duration,
+    pub warmup: Duration,
+    pub stages: Option<StagePlan>,
+    pub request_timeout: Option<Duration>,
+    pub max_requests_per_conn: Option<u64>,
+    pub pipeline_depth: usize,
+    pub tls: Option<TlsSettings>,
+}
+
+impl MioHTTPJob {
+    fn new_worker_stats(&self) -> WorkerStats {
+        let mut stats = WorkerStats::new(Duration::ZERO, 0, 0, 0, 0);
+        stats.set_endpoints(
+            self.endpoints
+                .iter()
+                .map(|name| EndpointStats::new(name))
+                .collect(),
+        );
+        if let Some(stages) = &self.stages {
+            stats.set_stages(
+                stages
+                    .stages
+                    .iter()
+                    .map(|stage| StageStats::new(&stage.name, stage.duration))
+                    .collect(),
+            );
+        }
+        stats.set_intervals(vec![], self.stats_interval);
+        stats
+    }
+
+    // the measured phase starts from fresh statistics on the already open connections
+    fn finish_warmup(
+        &self,
+        stats: &mut WorkerStats,
+        warmup_duration: Duration,
+        worker_requests: Option<u64>,
+        pool: &mut Slab<HTTPConnection>,
+    ) -> WorkerStats {
+        stats.run_duration = warmup_duration;
+        if let Some(worker_requests) = worker_requests {
+            assign_request_quotas(worker_requests, pool);
+        }
+        mem::replace(stats, self.new_worker_stats())
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        match &self.stages {
+            Some(stages) => Some(stages.duration()),
+            None => self
+                .job_duration_sec
+                .map(|duration| Duration::from_secs(duration as u64)),
+        }
+    }
+
+    fn rate_plan(&self) -> Option<StagePlan> {
+        match &self.stages {
+            Some(stages) if stages.unit == StageUnit::Rate => Some(stages.clone()),
+            Some(_) => None,
+            None => self.request_rate.map(|rate| {
+                let duration = self
+                    .duration()
+                    .map_or(Duration::MAX, |duration| self.warmup + duration);
+                StagePlan::constant_rate(rate, duration)
+            }),
+        }
+    }
+
+    fn connection_plan(&self) -> Option<&StagePlan> {
+        self.stages
+            .as_ref()
+            .filter(|stages| stages.unit == StageUnit::Connections)
+    }
 }
 
 impl CloneJob for MioHTTPJob {
-    fn clone_job<'a>(&self) -> Box<dyn Job + Send + Sync> {
+    fn clone_job(&self) -> Box<dyn Job + Send + Sync> {
         Box::new(self.clone())
     }
 }
@@ -128,79 +888,451 @@ impl CloneJob for MioHTTPJob {
 impl Job for MioHTTPJob {
     fn execute(
         &mut self,
-        stats_sender: std::sync::mpsc::Sender<crate::statistics::stats::WorkerStats>,
+        worker_index: usize,
+        stats_sender: std::sync::mpsc::Sender<WorkerMessage>,
     ) {
-        let mut poll = Poll::new().expect("unable to create poll");
-        let mut events = Events::with_capacity(self.conn_quantity);
-        let mut connections_slab: Slab<HTTPConnection> = Slab::new();
-        let request = self.parsed_url.compile_request();
-        let socket_address = format!("{}:{}", self.parsed_url.host, self.parsed_url.port)
-            .to_socket_addrs()
-            .expect("can not resolve hostname")
-            .next()
-            .expect("there is no host with this name");
-        fill_connection_slab(
-            self.conn_quantity,
-            socket_address,
-            &mut connections_slab,
-            &mut poll,
-        );
-        let mut request_count: u32 = 0;
-        let mut received_data = 0;
-        let mut bad_requests: u32 = 0;
-        let mut errors: u32 = 0;
-        let mut latencies = vec![0f64; 0];
+        let rate_plan = self.rate_plan();
+        let connection_plan = self.connection_plan().cloned();
+        let initial_connections = match &connection_plan {
+            Some(_) => 0,
+            None => self.conn_quantity,
+        };
+        let events_capacity = match &connection_plan {
+            Some(connection_plan) => connection_plan.max_target().ceil() as usize,
+            None => self.conn_quantity,
+        };
+        let mut events = Events::with_capacity(events_capacity.max(1));
+        let mut pool = ConnectionPool {
+            slab: Slab::new(),
+            poll: Poll::new().expect("unable to create poll"),
+            socket_addr: self.socket_address,
+            tls: self.tls.clone(),
+            limits: ConnectionLimits {
+                max_requests: self.max_requests_per_conn,
+                pipeline_depth: self.pipeline_depth,
+            },
+            target: usize::MAX,
+            paced: rate_plan.is_some(),
+            request: vec![],
+            connect_errors: vec![],
+        };
+        pool.fill(initial_connections);
+        let worker_requests = self
+            .worker_requests
+            .as_ref()
+            .map(|worker_requests| worker_requests[worker_index]);
+        let mut stats = self.new_worker_stats();
+        let mut next_progress_interval: u32 = 1;
         let start_time = Instant::now();
+        let warmup_end = start_time + self.warmup;
+        let deadline = self.duration().map(|duration| warmup_end + duration);
+        let mut warming_up = !self.warmup.is_zero();
+        let mut phase_start = start_time;
+        if !warming_up {
+            if let Some(worker_requests) = worker_requests {
+                assign_request_quotas(worker_requests, &mut pool.slab);
+            }
+        }
+        let mut next_request = match &rate_plan {
+            Some(rate_plan) => schedule_connections(start_time, rate_plan, &mut pool.slab),
+            None => 0,
+        };
         loop {
-            if start_time.elapsed().as_secs() >= self.job_duration_sec as u64 {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
                 break;
             }
-            poll.poll(&mut events, None)
+            // a warmup interval cut short by the end of the warmup is not reported
+            let progress_end = match warming_up {
+                true => Instant::now().min(warmup_end),
+                false => Instant::now(),
+            };
+            while progress_end - phase_start >= self.stats_interval * next_progress_interval {
+                let in_flight = pool
+                    .slab
+                    .iter()
+                    .map(|(_, connection)| connection.pipeline.len())
+                    .sum();
+                let snapshot = ProgressSnapshot {
+                    worker: worker_index,
+                    warmup: warming_up,
+                    interval: next_progress_interval as usize - 1,
+                    stats: stats
+                        .intervals
+                        .get(next_progress_interval as usize - 1)
+                        .cloned()
+                        .unwrap_or_default(),
+                    in_flight,
+                };
+                stats_sender.send(WorkerMessage::Progress(snapshot)).ok();
+                next_progress_interval += 1;
+            }
+            if warming_up && Instant::now() >= warmup_end {
+                let warmup_stats = self.finish_warmup(
+                    &mut stats,
+                    start_time.elapsed(),
+                    worker_requests,
+                    &mut pool.slab,
+                );
+                stats_sender
+                    .send(WorkerMessage::WarmupFinished(Box::new(warmup_stats)))
+                    .unwrap();
+                warming_up = false;
+                phase_start = warmup_end;
+                next_progress_interval = 1;
+            }
+            if !warming_up
+                && worker_requests.is_some()
+                && pool.slab.iter().all(|(_, connection)| {
+                    !connection.has_requests_left() && !connection.awaiting_response()
+                })
+            {
+                break;
+            }
+            if deadline.is_none()
+                && !pool.slab.is_empty()
+                && pool
+                    .slab
+                    .iter()
+                    .all(|(_, connection)| connection.failed_connects >= MAX_FAILED_CONNECTS)
+            {
+                break;
+            }
+            let now = Instant::now();
+            let phase_end = match warming_up {
+                true => Some(warmup_end),
+                false => deadline,
+            };
+            let mut timeout = (phase_start + self.stats_interval * next_progress_interval)
+                .saturating_duration_since(now);
+            if let Some(phase_end) = phase_end {
+                timeout = timeout.min(phase_end.saturating_duration_since(now));
+            }
+            if let Some(connection_plan) = &connection_plan {
+                timeout = timeout.min(CONNECTION_RAMP_TICK);
+                pool.follow_target(
+                    connection_plan.target_at(start_time.elapsed()).round() as usize,
+                );
+            }
+            if let Some(request_timeout) = self.request_timeout {
+                if let Some(response_timeout) =
+                    time_until_next_timeout(&pool.slab, request_timeout, now)
+                {
+                    timeout = timeout.min(response_timeout);
+                }
+            }
+            if rate_plan.is_some() {
+                if let Some(send_timeout) = time_until_next_send(&pool.slab, now) {
+                    timeout = timeout.min(send_timeout);
+                }
+            }
+            if let Some(reconnect_timeout) = time_until_next_reconnect(&pool.slab, now) {
+                timeout = timeout.min(reconnect_timeout);
+            }
+            pool.poll
+                .poll(&mut events, Some(timeout))
                 .expect("can not execute poll operation");
+            let mut interval_index =
+                (phase_start.elapsed().as_nanos() / self.stats_interval.as_nanos()) as usize;
+            // responses read right after the end of a phase still belong to its last interval
+            let phase_duration = match warming_up {
+                true => Some(self.warmup),
+                false => self.duration(),
+            };
+            if let Some(phase_duration) = phase_duration {
+                let intervals = phase_duration
+                    .as_nanos()
+                    .div_ceil(self.stats_interval.as_nanos())
+                    as usize;
+                interval_index = interval_index.min(intervals.max(1) - 1);
+            }
+            let stage_index = self
+                .stages
+                .as_ref()
+                .and_then(|stages| stages.stage_at(start_time.elapsed()));
             for event in &events {
-                let token = event.token();
-                let connection = connections_slab.get_mut(token.0).unwrap();
-                if event.is_readable() {
-                    latencies
-                        .push(connection.request_sent_time.unwrap().elapsed().as_micros() as f64);
-                    match connection.read_available() {
-                        HTTPReadREsult::Complete(response_size, status_first_char) => {
-                            received_data += response_size;
-                            if status_first_char != '2' && status_first_char != '3' {
-                                bad_requests += 1
-                            }
-                        }
-                        HTTPReadREsult::Error => {
-                            errors += 1;
+                pool.handle_event(
+                    event,
+                    &mut self.request_generator,
+                    &mut stats,
+                    interval_index,
+                    stage_index,
+                );
+            }
+            if let Some(request_timeout) = self.request_timeout {
+                pool.replace_timed_out(request_timeout, &mut stats, interval_index, stage_index);
+            }
+            pool.reconnect_due(Instant::now());
+            if let Some(rate_plan) = &rate_plan {
+                let now = Instant::now();
+                let mut failed = vec![];
+                for (token, connection) in pool.slab.iter_mut() {
+                    if connection.is_due(now) {
+                        let next_send_time = rate_plan
+                            .request_time(next_request)
+                            .map(|offset| start_time + offset);
+                        next_request += 1;
+                        if let Err((kind, endpoint)) = connection.send_scheduled_request(
+                            &mut self.request_generator,
+                            &mut pool.request,
+                            next_send_time,
+                        ) {
+                            stats.record_error(kind, interval_index, stage_index, endpoint);
+                            failed.push(token);
                         }
-                        _ => {}
                     }
                 }
-                if event.is_writable() {
-                    connection.send_request(request.as_bytes());
-                }
-                if event.is_read_closed() || event.is_write_closed() {
-                    request_count += connection.parser.responses_parsed as u32;
-                    reregister_socket_in_slab(
-                        socket_address,
-                        token,
-                        &mut connections_slab,
-                        &mut poll,
-                    );
+                // replaced right away so the broken socket does not report the failure again
+                for token in failed {
+                    pool.replace_connection(Token(token));
                 }
             }
+            for kind in pool.connect_errors.drain(..) {
+                stats.record_error(kind, interval_index, stage_index, None);
+            }
         }
-        for (_, connection) in connections_slab {
-            request_count += connection.parser.responses_parsed as u32
+        stats.run_duration = phase_start.elapsed();
+        stats_sender
+            .send(WorkerMessage::Finished(worker_index, Box::new(stats)))
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test_mio_job {
+    use std::time::{Duration, Instant};
+
+    use mio::{Poll, Token};
+    use slab::Slab;
+
+    use super::{
+        assign_request_quotas, find_timed_out, reconnect_delay, record_response,
+        reregister_socket_in_slab, schedule_connections, ConnectionLimits, HTTPConnection,
+        InFlightRequest, MioHTTPJob,
+    };
+    use crate::http_parser::http_parser::ParsedResponse;
+    use crate::request_generator::generator::create_request_generator;
+    use crate::stages::StagePlan;
+    use crate::statistics::stats::{RequestError, WorkerStats};
+    use crate::url_parser::ParsedUrlHeader;
+
+    const LIMITS: ConnectionLimits = ConnectionLimits {
+        max_requests: None,
+        pipeline_depth: 1,
+    };
+
+    fn pool_of(size: usize) -> Slab<HTTPConnection> {
+        let mut pool = Slab::new();
+        for _ in 0..size {
+            pool.insert(HTTPConnection::new(LIMITS));
+        }
+        pool
+    }
+
+    fn request_at(sent_time: Instant, intended_send_time: Option<Instant>) -> InFlightRequest {
+        InFlightRequest {
+            sent_time,
+            intended_send_time,
+            first_byte_time: None,
+            endpoint: 0,
+        }
+    }
+
+    fn job() -> MioHTTPJob {
+        let parsed_url = ParsedUrlHeader::parse_url("127.0.0.1:8000/").unwrap();
+        MioHTTPJob {
+            socket_address: "127.0.0.1:8000".parse().unwrap(),
+            request_generator: create_request_generator(&parsed_url, false, None).unwrap(),
+            endpoints: vec!["GET /".to_string()],
+            job_duration_sec: Some(10),
+            worker_requests: None,
+            conn_quantity: 2,
+            request_rate: None,
+            stats_interval: Duration::from_secs(1),
+            warmup: Duration::from_secs(5),
+            stages: None,
+            request_timeout: None,
+            max_requests_per_conn: None,
+            pipeline_depth: 1,
+            tls: None,
+        }
+    }
+
+    #[test]
+    fn test_connections_scheduled_at_the_rate() {
+        let start_time = Instant::now();
+        let mut pool = pool_of(4);
+        let plan = StagePlan::constant_rate(100.0, Duration::from_secs(10));
+        assert_eq!(schedule_connections(start_time, &plan, &mut pool), 4);
+        let send_times: Vec<Duration> = pool
+            .iter()
+            .map(|(_, connection)| connection.next_send_time.unwrap() - start_time)
+            .collect();
+        assert_eq!(
+            send_times,
+            [0, 10, 20, 30].map(Duration::from_millis).to_vec()
+        );
+        let connection = &mut pool[1];
+        connection.connected = true;
+        assert!(!connection.is_due(start_time));
+        assert!(connection.is_due(start_time + Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn test_corrected_latency_of_late_request() {
+        let intended_send_time = Instant::now();
+        // sent 50ms after its turn and answered 10ms later
+        let request = request_at(
+            intended_send_time + Duration::from_millis(50),
+            Some(intended_send_time),
+        );
+        let mut stats = WorkerStats::new(Duration::ZERO, 0, 0, 0, 0);
+        let response = ParsedResponse { status_code: 200 };
+        let response_time = intended_send_time + Duration::from_millis(60);
+        record_response(&mut stats, &response, &request, response_time, 0, None);
+        assert_eq!(stats.latencies.max(), 10_000);
+        assert_eq!(stats.corrected_latencies.max(), 60_000);
+        assert_eq!(stats.intervals[0].latencies.max(), 60_000);
+        assert_eq!(stats.request_count, 1);
+    }
+
+    #[test]
+    fn test_statistics_reset_after_warmup() {
+        let job = job();
+        let mut stats = job.new_worker_stats();
+        let now = Instant::now();
+        let response = ParsedResponse { status_code: 503 };
+        record_response(&mut stats, &response, &request_at(now, None), now, 4, None);
+        stats.record_error(RequestError::ConnectRefused, 4, None, Some(0));
+        let mut pool = pool_of(2);
+        pool[0].pipeline.push(request_at(now, None), false);
+        let warmup_stats =
+            job.finish_warmup(&mut stats, Duration::from_secs(5), Some(10), &mut pool);
+        assert_eq!(warmup_stats.run_duration, Duration::from_secs(5));
+        assert_eq!(warmup_stats.request_count, 1);
+        assert_eq!(warmup_stats.error_count, 1);
+        assert_eq!(warmup_stats.latencies.count(), 1);
+        assert_eq!(stats.request_count, 0);
+        assert_eq!(stats.error_count, 0);
+        assert_eq!(stats.bad_requests, 0);
+        assert!(stats.errors_by_kind.is_empty());
+        assert!(stats.status_codes.is_empty());
+        assert!(stats.intervals.is_empty());
+        assert_eq!(stats.latencies.count(), 0);
+        assert_eq!(stats.endpoints[0].name, "GET /");
+        assert_eq!(stats.endpoints[0].request_count, 0);
+        assert_eq!(stats.endpoints[0].latencies.count(), 0);
+        // the open connections carry over, the awaited response counts towards -n
+        assert_eq!(pool.len(), 2);
+        assert_eq!(pool[0].pipeline.len(), 1);
+        assert_eq!(pool[0].requests_left, Some(4));
+        assert_eq!(pool[1].requests_left, Some(5));
+    }
+
+    #[test]
+    fn test_failed_send_returns_request_to_quota() {
+        let mut connection = HTTPConnection::new(LIMITS);
+        connection.requests_left = Some(3);
+        // without a socket the write fails right away
+        let error = connection.send_request(b"HEAD / HTTP/1.1\r\n\r\n", 1, None);
+        assert_eq!(error.unwrap_err().1, Some(1));
+        assert_eq!(connection.requests_left, Some(3));
+        assert_eq!(connection.requests_sent, 0);
+        assert!(connection.pipeline.is_empty());
+        assert!(connection.write_buffer.is_empty());
+        assert!(connection.pipeline.is_idle());
+    }
+
+    #[test]
+    fn test_quota_carried_over_to_new_connection() {
+        let now = Instant::now();
+        let mut pool = pool_of(2);
+        pool[0].pipeline.push(request_at(now, None), false);
+        assign_request_quotas(9, &mut pool);
+        assert_eq!(pool[0].requests_left, Some(4));
+        assert_eq!(pool[1].requests_left, Some(4));
+        let mut poll = Poll::new().unwrap();
+        let socket_addr = "127.0.0.1:9".parse().unwrap();
+        // the connect never succeeded, so the new connection waits before connecting
+        reregister_socket_in_slab(socket_addr, None, Token(0), &mut pool, &mut poll).unwrap();
+        assert_eq!(pool[0].requests_left, Some(5));
+        assert!(pool[0].pipeline.is_empty());
+        assert!(pool[0].tcp_stream.is_none());
+        assert_eq!(pool[0].failed_connects, 1);
+    }
+
+    #[test]
+    fn test_requests_lost_with_closed_pipeline_are_sent_again() {
+        let now = Instant::now();
+        let mut pool = pool_of(1);
+        pool[0].requests_left = Some(5);
+        for _ in 0..3 {
+            pool[0].pipeline.push(request_at(now, None), false);
         }
-        let mut worker_statistics = WorkerStats::new(
-            self.job_duration_sec,
-            request_count,
-            errors,
-            bad_requests,
-            received_data,
+        let mut responses = vec![];
+        pool[0].pipeline.read(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\nHTTP/1.1 200 OK\r\nContent-Le",
+            now,
+            &mut responses,
         );
-        worker_statistics.calculate_latencies(latencies);
-        stats_sender.send(worker_statistics).unwrap();
+        pool[0].pipeline.close(now, &mut responses);
+        assert_eq!(responses.len(), 1);
+        assert_eq!(pool[0].pipeline.len(), 2);
+        let mut poll = Poll::new().unwrap();
+        let socket_addr = "127.0.0.1:9".parse().unwrap();
+        reregister_socket_in_slab(socket_addr, None, Token(0), &mut pool, &mut poll).unwrap();
+        assert_eq!(pool[0].requests_left, Some(7));
+        assert!(pool[0].pipeline.is_empty());
+        assert!(pool[0].pipeline.is_idle());
+    }
+
+    #[test]
+    fn test_timed_out_request_is_not_a_latency() {
+        let sent_time = Instant::now();
+        let mut pool = pool_of(2);
+        for (_, connection) in pool.iter_mut() {
+            connection.connected = true;
+        }
+        pool[1]
+            .pipeline
+            .push(request_at(sent_time, Some(sent_time)), false);
+        let now = sent_time + Duration::from_secs(2);
+        assert!(find_timed_out(&pool, Duration::from_secs(3), now).is_empty());
+        let timed_out = find_timed_out(&pool, Duration::from_secs(1), now);
+        assert_eq!(timed_out, [(1, RequestError::ReadTimeout, Some(0))]);
+        let mut stats = job().new_worker_stats();
+        for (_, kind, endpoint) in timed_out {
+            stats.record_error(kind, 0, None, endpoint);
+        }
+        assert_eq!(stats.errors_by_kind[&RequestError::ReadTimeout], 1);
+        assert_eq!(stats.endpoints[0].error_count, 1);
+        assert_eq!(stats.request_count, 0);
+        for latencies in [
+            &stats.latencies,
+            &stats.corrected_latencies,
+            &stats.first_byte_latencies,
+            &stats.download_latencies,
+            &stats.endpoints[0].latencies,
+            &stats.intervals[0].latencies,
+        ] {
+            assert_eq!(latencies.count(), 0);
+        }
+    }
+
+    #[test]
+    fn test_tls_failures_classified() {
+        let connection = HTTPConnection::new(LIMITS);
+        let error = std::io::Error::other(rustls::Error::DecryptError);
+        assert_eq!(connection.classify_error(&error), RequestError::TlsError);
+        let error = std::io::Error::other("not a tls failure");
+        assert_eq!(connection.classify_error(&error), RequestError::Other);
+    }
+
+    #[test]
+    fn test_reconnect_delay_doubles_up_to_a_second() {
+        let delays: Vec<u64> = (1..=9)
+            .map(|failed_connects| reconnect_delay(failed_connects).as_millis() as u64)
+            .collect();
+        assert_eq!(delays, [10, 20, 40, 80, 160, 320, 640, 1000, 1000]);
+        assert_eq!(reconnect_delay(u32::MAX), Duration::from_secs(1));
     }
 }
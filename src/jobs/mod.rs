@@ -1,2 +1,3 @@
 pub mod job;
 pub mod mio_job;
+pub mod pipeline;
@@ -0,0 +1,206 @@
+use std::{collections::VecDeque, time::Instant};
+
+use crate::http_parser::http_parser::{HTTParser, ParsedResponse, ParserState};
+
+// a request written to the connection whose response has not been read yet
+pub struct InFlightRequest {
+    pub sent_time: Instant,
+    pub intended_send_time: Option<Instant>,
+    pub first_byte_time: Option<Instant>,
+    pub endpoint: usize,
+}
+
+// a response with its request and the time its last byte was read
+pub type MatchedResponse = (ParsedResponse, InFlightRequest, Instant);
+
+// requests written to a connection, matched in order with the responses read from it
+#[derive(Default)]
+pub struct Pipeline {
+    parser: HTTParser,
+    requests: VecDeque<InFlightRequest>,
+}
+
+impl Pipeline {
+    pub fn push(&mut self, request: InFlightRequest, head_request: bool) {
+        self.parser.expect_response(head_request);
+        self.requests.push_back(request);
+    }
+
+    // the last request was never written, so its response will not arrive
+    pub fn cancel_last(&mut self) -> Option<InFlightRequest> {
+        self.parser.forget_expected_response();
+        self.requests.pop_back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    pub fn oldest(&self) -> Option<&InFlightRequest> {
+        self.requests.front()
+    }
+
+    pub fn is_invalid(&self) -> bool {
+        self.parser.state == ParserState::Invalid
+    }
+
+    // no response is expected and none has been partially read
+    pub fn is_idle(&self) -> bool {
+        self.parser.is_idle()
+    }
+
+    pub fn read(&mut self, data: &[u8], read_time: Instant, responses: &mut Vec<MatchedResponse>) {
+        let parsed = self.parser.parse(data);
+        self.match_responses(parsed, read_time, responses);
+        // the rest of the data is the start of the next response
+        if self.parser.state != ParserState::Started {
+            if let Some(request) = self.requests.front_mut() {
+                request.first_byte_time.get_or_insert(read_time);
+            }
+        }
+    }
+
+    // a response delimited by the end of the connection is complete once it closes
+    pub fn close(&mut self, close_time: Instant, responses: &mut Vec<MatchedResponse>) {
+        let finished = self.parser.finish();
+        self.match_responses(finished, close_time, responses);
+    }
+
+    fn match_responses(
+        &mut self,
+        parsed: impl IntoIterator<Item = ParsedResponse>,
+        read_time: Instant,
+        responses: &mut Vec<MatchedResponse>,
+    ) {
+        for response in parsed {
+            let Some(mut request) = self.requests.pop_front() else {
+                continue;
+            };
+            request.first_byte_time.get_or_insert(read_time);
+            responses.push((response, request, read_time));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_pipeline {
+    use std::time::{Duration, Instant};
+
+    use super::{InFlightRequest, Pipeline};
+
+    fn request(endpoint: usize, sent_time: Instant) -> InFlightRequest {
+        InFlightRequest {
+            sent_time,
+            intended_send_time: None,
+            first_byte_time: None,
+            endpoint,
+        }
+    }
+
+    fn endpoints(responses: &[super::MatchedResponse]) -> Vec<usize> {
+        responses
+            .iter()
+            .map(|(_, request, _)| request.endpoint)
+            .collect()
+    }
+
+    #[test]
+    fn test_responses_matched_in_order() {
+        let start = Instant::now();
+        let mut pipeline = Pipeline::default();
+        for endpoint in 0..3 {
+            pipeline.push(request(endpoint, start), false);
+        }
+        let mut responses = vec![];
+        let first_read = start + Duration::from_millis(5);
+        pipeline.read(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nokHTTP/1.1 404 Not Found\r\nContent-Le",
+            first_read,
+            &mut responses,
+        );
+        assert_eq!(endpoints(&responses), [0]);
+        // the second response started in the first read
+        assert_eq!(pipeline.oldest().unwrap().first_byte_time, Some(first_read));
+        let second_read = start + Duration::from_millis(8);
+        pipeline.read(
+            b"ngth: 0\r\n\r\nHTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n",
+            second_read,
+            &mut responses,
+        );
+        assert_eq!(endpoints(&responses), [0, 1, 2]);
+        let status_codes: Vec<u16> = responses
+            .iter()
+            .map(|(response, _, _)| response.status_code)
+            .collect();
+        assert_eq!(status_codes, [200, 404, 503]);
+        assert_eq!(responses[1].1.first_byte_time, Some(first_read));
+        assert_eq!(responses[1].2, second_read);
+        assert!(pipeline.is_empty());
+        assert!(pipeline.is_idle());
+    }
+
+    #[test]
+    fn test_head_responses_have_no_body() {
+        let start = Instant::now();
+        let mut pipeline = Pipeline::default();
+        pipeline.push(request(0, start), true);
+        pipeline.push(request(1, start), false);
+        pipeline.push(request(2, start), true);
+        let mut responses = vec![];
+        pipeline.read(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n\
+HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello\
+HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n",
+            start,
+            &mut responses,
+        );
+        assert_eq!(endpoints(&responses), [0, 1, 2]);
+        assert!(pipeline.is_idle());
+    }
+
+    #[test]
+    fn test_close_in_the_middle_of_the_pipeline() {
+        let start = Instant::now();
+        let mut pipeline = Pipeline::default();
+        for endpoint in 0..3 {
+            pipeline.push(request(endpoint, start), false);
+        }
+        let mut responses = vec![];
+        pipeline.read(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\nHTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nhal",
+            start,
+            &mut responses,
+        );
+        pipeline.close(start, &mut responses);
+        assert_eq!(endpoints(&responses), [0]);
+        // the requests left are lost with the connection
+        assert_eq!(pipeline.len(), 2);
+        assert_eq!(pipeline.oldest().unwrap().endpoint, 1);
+        assert!(!pipeline.is_idle());
+    }
+
+    #[test]
+    fn test_cancelled_request_expects_no_response() {
+        let start = Instant::now();
+        let mut pipeline = Pipeline::default();
+        pipeline.push(request(0, start), false);
+        pipeline.push(request(1, start), true);
+        assert_eq!(pipeline.cancel_last().unwrap().endpoint, 1);
+        pipeline.push(request(2, start), false);
+        let mut responses = vec![];
+        // the second body would be read as a response if the HEAD request was still expected
+        pipeline.read(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok\
+HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+            start,
+            &mut responses,
+        );
+        assert_eq!(endpoints(&responses), [0, 2]);
+        assert!(!pipeline.is_invalid());
+        assert!(pipeline.is_idle());
+    }
+}
@@ -1,40 +1,246 @@
+use std::{
+    net::{SocketAddr, ToSocketAddrs},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
 mod cli_args;
 pub mod http_parser;
 mod jobs;
+mod reporters;
+mod request_generator;
+mod scenario;
+mod stages;
 mod statistics;
 mod threadpool;
+mod thresholds;
+mod tls;
 mod url_parser;
 pub mod utils;
 
-use cli_args::get_parsed_args;
+use cli_args::{get_parsed_args, CliArgs};
+use colored::Colorize;
 use jobs::mio_job::MioHTTPJob;
+use reporters::{
+    csv::CsvReporter, json::JsonReporter, junit::JUnitReporter, progress::ProgressPrinter,
+    text::TextReporter, OutputFormat, Reporter, RunConfig, RunInfo,
+};
+use request_generator::generator::{create_request_generator, create_scenario_generator};
+use request_generator::template::CsvTable;
+use scenario::load_scenario;
+use stages::StagePlan;
 use threadpool::ThreadPool;
+use thresholds::{evaluate_thresholds, Threshold};
+use tls::TlsSettings;
+
+use url_parser::{ParsedUrlHeader, Scheme};
+use utils::split_evenly;
+
+const THRESHOLDS_FAILED_EXIT_CODE: i32 = 99;
 
-use url_parser::ParsedUrlHeader;
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("{} {}", "error:".red().bold(), message);
+    std::process::exit(2);
+}
+
+// every connection uses the first address of the host, resolved once before the run
+fn resolve_host(parsed_url: &ParsedUrlHeader) -> Result<(SocketAddr, Duration), String> {
+    let dns_start_time = Instant::now();
+    let address = format!("{}:{}", parsed_url.host, parsed_url.port);
+    let socket_address = address
+        .to_socket_addrs()
+        .map_err(|e| format!("can not resolve {address}: {e}"))?
+        .next()
+        .ok_or_else(|| format!("there is no host with the name {}", parsed_url.host))?;
+    Ok((socket_address, dns_start_time.elapsed()))
+}
 
-fn run_pool(url: &str, header: Option<String>, duration: usize, threads: u8, connections: usize) {
-    let mut parsed_url = ParsedUrlHeader::parse_url(url).expect("can not parse url");
-    if header.is_some() {
-        parsed_url
-            .add_header(header.unwrap())
-            .expect("invalid header provided");
+fn create_reporters(cli_args: &CliArgs) -> Vec<Box<dyn Reporter>> {
+    let mut reporters: Vec<Box<dyn Reporter>> = vec![];
+    match cli_args.output_format {
+        OutputFormat::Text => reporters.push(Box::new(TextReporter {
+            show_warmup: cli_args.show_warmup,
+        })),
+        OutputFormat::Json => reporters.push(Box::new(JsonReporter { output_file: None })),
     }
+    if let Some(output_file) = &cli_args.output_file {
+        reporters.push(Box::new(JsonReporter {
+            output_file: Some(output_file.clone()),
+        }));
+    }
+    if let Some(timeseries_file) = &cli_args.timeseries_file {
+        reporters.push(Box::new(CsvReporter {
+            output_file: timeseries_file.clone(),
+        }));
+    }
+    if let Some(junit_file) = &cli_args.junit_file {
+        reporters.push(Box::new(JUnitReporter {
+            output_file: junit_file.clone(),
+        }));
+    }
+    reporters
+}
+
+// returns whether every threshold passed
+fn run_pool(cli_args: CliArgs) -> bool {
+    let run_config = RunConfig {
+        url: cli_args.url.clone(),
+        method: cli_args.method.to_uppercase(),
+        threads: cli_args.threads,
+        connections: cli_args.connections,
+        duration_sec: cli_args.duration,
+        requests: cli_args.requests,
+        rate: cli_args.rate,
+        interval_sec: cli_args.interval.as_secs_f64(),
+        warmup_sec: cli_args.warmup.as_secs_f64(),
+        stages: cli_args.stages.as_ref().map(StagePlan::names),
+        timeout_sec: cli_args.timeout.map(|timeout| timeout.as_secs_f64()),
+        keep_alive: cli_args.keep_alive,
+        max_requests_per_conn: cli_args.max_requests_per_conn,
+        pipeline: cli_args.pipeline,
+        headers: cli_args.headers.clone(),
+        scenario: cli_args.scenario_file.clone(),
+        thresholds: cli_args.thresholds.clone(),
+    };
+    let reporters = create_reporters(&cli_args);
+    let thresholds: Vec<Threshold> = cli_args
+        .thresholds
+        .iter()
+        .map(|threshold| Threshold::parse(threshold).unwrap_or_else(|e| exit_with_error(&e)))
+        .collect();
+    let mut parsed_url = ParsedUrlHeader::parse_url(&cli_args.url)
+        .unwrap_or_else(|e| exit_with_error(&format!("can not parse url: {e}")));
+    for header in cli_args.headers.iter().cloned() {
+        if let Err(e) = parsed_url.add_header(header) {
+            exit_with_error(&e);
+        }
+    }
+    parsed_url.default_headers = cli_args.default_headers;
+    parsed_url.keep_alive = cli_args.keep_alive;
+    if let Err(e) = parsed_url.set_method(&cli_args.method) {
+        exit_with_error(&e);
+    }
+    if let Some(body) = cli_args.body.clone() {
+        parsed_url.set_body(body);
+    }
+    let csv = cli_args
+        .csv_file
+        .as_deref()
+        .map(CsvTable::load)
+        .transpose()
+        .unwrap_or_else(|e| exit_with_error(&e))
+        .map(Arc::new);
+    let (request_generator, endpoints) = match &cli_args.scenario_file {
+        Some(scenario_file) => {
+            let endpoints =
+                load_scenario(scenario_file, &parsed_url).unwrap_or_else(|e| exit_with_error(&e));
+            let names = endpoints.iter().map(|e| e.name.clone()).collect();
+            (create_scenario_generator(&endpoints, csv.as_ref()), names)
+        }
+        None => (
+            create_request_generator(&parsed_url, !cli_args.binary_body, csv.as_ref()),
+            vec![],
+        ),
+    };
+    let request_generator = request_generator
+        .unwrap_or_else(|e| exit_with_error(&format!("invalid request template: {e}")));
+    let tls = match parsed_url.scheme {
+        Scheme::Https => Some(
+            TlsSettings::new(&parsed_url.host, &cli_args.tls_options)
+                .unwrap_or_else(|e| exit_with_error(&e)),
+        ),
+        Scheme::Http => None,
+    };
+    let (socket_address, dns_lookup_time) =
+        resolve_host(&parsed_url).unwrap_or_else(|e| exit_with_error(&e));
     let job2 = MioHTTPJob {
-        parsed_url: parsed_url.clone(),
-        job_duration_sec: duration,
-        conn_quantity: connections,
+        socket_address,
+        request_generator,
+        endpoints,
+        job_duration_sec: cli_args.duration,
+        worker_requests: cli_args
+            .requests
+            .map(|requests| split_evenly(requests, cli_args.threads as usize)),
+        conn_quantity: cli_args.connections,
+        request_rate: cli_args
+            .rate
+            .map(|rate| rate as f64 / cli_args.threads as f64),
+        stats_interval: cli_args.interval,
+        warmup: cli_args.warmup,
+        stages: cli_args
+            .stages
+            .as_ref()
+            .map(|stages| stages.per_worker(cli_args.threads as usize)),
+        request_timeout: cli_args.timeout,
+        max_requests_per_conn: cli_args.max_requests_per_conn,
+        pipeline_depth: cli_args.pipeline,
+        tls,
     };
-    let th_pool: ThreadPool = ThreadPool::new(threads);
-    th_pool.start(Box::new(job2));
+    if cli_args.output_format == OutputFormat::Text {
+        println!(
+            "{}",
+            format!("Spawning workers: {}", cli_args.threads)
+                .cyan()
+                .bold()
+        );
+        if !cli_args.warmup.is_zero() {
+            println!(
+                "{}",
+                format!(
+                    "Warming up for {:.1}s, its results are discarded",
+                    cli_args.warmup.as_secs_f64()
+                )
+                .cyan()
+            );
+        }
+    }
+    let th_pool: ThreadPool = ThreadPool::new(cli_args.threads);
+    let started_at = SystemTime::now();
+    let mut progress_printer = ProgressPrinter::new(cli_args.threads as usize, cli_args.interval);
+    let mut summary = match cli_args.output_format {
+        OutputFormat::Text => {
+            let summary = th_pool.start(Box::new(job2), &mut |message| {
+                progress_printer.on_message(message)
+            });
+            progress_printer.finish();
+            summary
+        }
+        OutputFormat::Json => th_pool.start(Box::new(job2), &mut |_| {}),
+    };
+    summary.set_dns_lookup_time(dns_lookup_time);
+    let run = RunInfo {
+        config: run_config,
+        started_at,
+        finished_at: SystemTime::now(),
+        threshold_results: evaluate_thresholds(&thresholds, &summary),
+    };
+    for reporter in reporters {
+        if let Err(e) = reporter.report(&summary, &run) {
+            exit_with_error(&e);
+        }
+    }
+    let failed: Vec<_> = run
+        .threshold_results
+        .iter()
+        .filter(|result| !result.passed)
+        .collect();
+    if cli_args.output_format == OutputFormat::Json {
+        for result in &failed {
+            eprintln!(
+                "{} threshold {} failed: {} was {}",
+                "error:".red().bold(),
+                result.expression,
+                result.metric,
+                result.actual
+            );
+        }
+    }
+    failed.is_empty()
 }
 
 fn main() {
-    let cli_args = get_parsed_args();
-    run_pool(
-        &cli_args.url,
-        cli_args.header,
-        cli_args.duration,
-        cli_args.threads,
-        cli_args.connections,
-    );
+    let cli_args = get_parsed_args().unwrap_or_else(|e| exit_with_error(&e));
+    if !run_pool(cli_args) {
+        std::process::exit(THRESHOLDS_FAILED_EXIT_CODE);
+    }
 }
@@ -0,0 +1,83 @@
+use std::fs;
+
+use super::{Reporter, RunInfo};
+use crate::statistics::stats::SummaryStatistics;
+
+const CSV_HEADER: &str = "interval_start_sec,requests,requests_per_second,errors,non_2xx_3xx,\
+received_bytes,mean_latency_us,p50_latency_us,p90_latency_us,p99_latency_us,max_latency_us";
+
+pub fn build_csv_report(summary: &SummaryStatistics) -> String {
+    let interval_secs = summary.interval_duration.as_secs_f64();
+    let mut report = format!("{CSV_HEADER}\n");
+    for (index, interval) in summary.intervals.iter().enumerate() {
+        let latencies = &interval.latencies;
+        report.push_str(&format!(
+            "{:.3},{},{:.2},{},{},{},{:.2},{},{},{},{}\n",
+            index as f64 * interval_secs,
+            interval.request_count,
+            interval.request_count as f64 / interval_secs,
+            interval.error_count,
+            interval.bad_requests,
+            interval.received_data,
+            latencies.mean(),
+            latencies.value_at_percentile(50.0),
+            latencies.value_at_percentile(90.0),
+            latencies.value_at_percentile(99.0),
+            latencies.max()
+        ));
+    }
+    report
+}
+
+pub struct CsvReporter {
+    pub output_file: String,
+}
+
+impl Reporter for CsvReporter {
+    fn report(&self, summary: &SummaryStatistics, _run: &RunInfo) -> Result<(), String> {
+        fs::write(&self.output_file, build_csv_report(summary))
+            .map_err(|e| format!("unable to write {}: {e}", self.output_file))
+    }
+}
+
+#[cfg(test)]
+mod test_csv_report {
+    use std::time::Duration;
+
+    use super::build_csv_report;
+    use crate::statistics::{
+        histogram::LatencyHistogram,
+        stats::{IntervalStats, SummaryStatistics, WorkerStats},
+    };
+
+    #[test]
+    fn test_csv_rows_per_interval() {
+        let mut latencies = LatencyHistogram::new();
+        latencies.record(100);
+        latencies.record(300);
+        let mut worker_stats = WorkerStats::new(Duration::from_secs(2), 3, 1, 0, 60);
+        worker_stats.set_intervals(
+            vec![
+                IntervalStats {
+                    request_count: 2,
+                    received_data: 40,
+                    latencies,
+                    ..Default::default()
+                },
+                IntervalStats {
+                    request_count: 1,
+                    error_count: 1,
+                    received_data: 20,
+                    ..Default::default()
+                },
+            ],
+            Duration::from_millis(500),
+        );
+        let report = build_csv_report(&SummaryStatistics::new(vec![worker_stats]));
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("interval_start_sec,requests,requests_per_second"));
+        assert_eq!(lines[1], "0.000,2,4.00,0,0,40,200.00,100,300,300,300");
+        assert_eq!(lines[2], "0.500,1,2.00,1,0,20,0.00,0,0,0,0");
+    }
+}
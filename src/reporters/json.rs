@@ -0,0 +1,357 @@
+use std::{collections::BTreeMap, fs};
+
+use serde::Serialize;
+
+use super::{Reporter, RunConfig, RunInfo};
+use crate::statistics::{
+    histogram::LatencyHistogram,
+    stats::{per_second, SummaryStatistics, REPORTED_PERCENTILES},
+};
+use crate::thresholds::ThresholdResult;
+use crate::utils::format_timestamp;
+
+// bump whenever a field is renamed, removed or changes its meaning
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct LatencyReport {
+    count: u64,
+    min_us: u64,
+    max_us: u64,
+    mean_us: f64,
+    stdev_us: f64,
+    percentiles_us: BTreeMap<String, u64>,
+}
+
+impl From<&LatencyHistogram> for LatencyReport {
+    fn from(latencies: &LatencyHistogram) -> Self {
+        LatencyReport {
+            count: latencies.count(),
+            min_us: latencies.min(),
+            max_us: latencies.max(),
+            mean_us: latencies.mean(),
+            stdev_us: latencies.stdev(),
+            percentiles_us: REPORTED_PERCENTILES
+                .iter()
+                .map(|percentile| {
+                    (
+                        format!("p{percentile}"),
+                        latencies.value_at_percentile(*percentile),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorsReport {
+    total: u32,
+    by_kind: BTreeMap<&'static str, u32>,
+}
+
+#[derive(Serialize)]
+struct PhasesReport {
+    tcp_connect: Option<LatencyReport>,
+    tls_handshake: Option<LatencyReport>,
+    first_byte: Option<LatencyReport>,
+    download: Option<LatencyReport>,
+}
+
+#[derive(Serialize)]
+struct SummaryReport {
+    duration_sec: f64,
+    requests: u32,
+    requests_per_second: f64,
+    received_bytes: usize,
+    non_2xx_3xx_responses: u32,
+    errors: ErrorsReport,
+    latency: LatencyReport,
+    corrected_latency: Option<LatencyReport>,
+    dns_lookup_us: Option<u64>,
+    phases: PhasesReport,
+}
+
+#[derive(Serialize)]
+struct EndpointReport<'a> {
+    name: &'a str,
+    requests: u32,
+    requests_per_second: f64,
+    non_2xx_3xx_responses: u32,
+    errors: u32,
+    latency: LatencyReport,
+}
+
+#[derive(Serialize)]
+struct StageReport<'a> {
+    name: &'a str,
+    duration_sec: f64,
+    requests: u32,
+    requests_per_second: f64,
+    non_2xx_3xx_responses: u32,
+    errors: u32,
+    latency: LatencyReport,
+}
+
+#[derive(Serialize)]
+struct IntervalReport {
+    start_sec: f64,
+    requests: u32,
+    requests_per_second: f64,
+    non_2xx_3xx_responses: u32,
+    errors: u32,
+    received_bytes: usize,
+    latency: LatencyReport,
+}
+
+#[derive(Serialize)]
+struct WorkerReport {
+    id: usize,
+    requests: u32,
+    non_2xx_3xx_responses: u32,
+    errors: u32,
+    received_bytes: usize,
+    latency: LatencyReport,
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    schema_version: u32,
+    started_at: String,
+    finished_at: String,
+    config: &'a RunConfig,
+    summary: SummaryReport,
+    warmup: Option<SummaryReport>,
+    thresholds: &'a [ThresholdResult],
+    status_codes: BTreeMap<String, u32>,
+    endpoints: Vec<EndpointReport<'a>>,
+    stages: Vec<StageReport<'a>>,
+    timeseries: Vec<IntervalReport>,
+    workers: Vec<WorkerReport>,
+}
+
+fn optional_latency(latencies: &LatencyHistogram) -> Option<LatencyReport> {
+    match latencies.count() {
+        0 => None,
+        _ => Some(latencies.into()),
+    }
+}
+
+impl From<&SummaryStatistics> for SummaryReport {
+    fn from(summary: &SummaryStatistics) -> Self {
+        SummaryReport {
+            duration_sec: summary.run_duration.as_secs_f64(),
+            requests: summary.total_requests,
+            requests_per_second: summary.rps,
+            received_bytes: summary.total_data_received,
+            non_2xx_3xx_responses: summary.non_200_300_requests,
+            errors: ErrorsReport {
+                total: summary.total_errors,
+                by_kind: summary
+                    .errors_by_kind
+                    .iter()
+                    .map(|(kind, count)| (kind.name(), *count))
+                    .collect(),
+            },
+            latency: (&summary.latencies).into(),
+            corrected_latency: optional_latency(&summary.corrected_latencies),
+            dns_lookup_us: summary
+                .dns_lookup_time
+                .map(|dns_lookup_time| dns_lookup_time.as_micros() as u64),
+            phases: PhasesReport {
+                tcp_connect: optional_latency(&summary.connect_latencies),
+                tls_handshake: optional_latency(&summary.handshake_latencies),
+                first_byte: optional_latency(&summary.first_byte_latencies),
+                download: optional_latency(&summary.download_latencies),
+            },
+        }
+    }
+}
+
+pub fn build_json_report(summary: &SummaryStatistics, run: &RunInfo) -> String {
+    let interval_secs = summary.interval_duration.as_secs_f64();
+    let report = JsonReport {
+        schema_version: SCHEMA_VERSION,
+        started_at: format_timestamp(run.started_at),
+        finished_at: format_timestamp(run.finished_at),
+        config: &run.config,
+        summary: summary.into(),
+        warmup: summary.warmup.as_deref().map(SummaryReport::from),
+        thresholds: &run.threshold_results,
+        status_codes: summary
+            .status_codes
+            .iter()
+            .map(|(status_code, count)| (status_code.to_string(), *count))
+            .collect(),
+        endpoints: summary
+            .endpoints
+            .iter()
+            .map(|endpoint| EndpointReport {
+                name: &endpoint.name,
+                requests: endpoint.request_count,
+                requests_per_second: per_second(endpoint.request_count, summary.run_duration),
+                non_2xx_3xx_responses: endpoint.bad_requests,
+                errors: endpoint.error_count,
+                latency: (&endpoint.latencies).into(),
+            })
+            .collect(),
+        stages: summary
+            .stages
+            .iter()
+            .map(|stage| StageReport {
+                name: &stage.name,
+                duration_sec: stage.duration.as_secs_f64(),
+                requests: stage.request_count,
+                requests_per_second: per_second(stage.request_count, stage.duration),
+                non_2xx_3xx_responses: stage.bad_requests,
+                errors: stage.error_count,
+                latency: (&stage.latencies).into(),
+            })
+            .collect(),
+        timeseries: summary
+            .intervals
+            .iter()
+            .enumerate()
+            .map(|(index, interval)| IntervalReport {
+                start_sec: index as f64 * interval_secs,
+                requests: interval.request_count,
+                requests_per_second: per_second(interval.request_count, summary.interval_duration),
+                non_2xx_3xx_responses: interval.bad_requests,
+                errors: interval.error_count,
+                received_bytes: interval.received_data,
+                latency: (&interval.latencies).into(),
+            })
+            .collect(),
+        workers: summary
+            .workers_stats
+            .iter()
+            .enumerate()
+            .map(|(id, worker)| WorkerReport {
+                id,
+                requests: worker.request_count,
+                non_2xx_3xx_responses: worker.bad_requests,
+                errors: worker.error_count,
+                received_bytes: worker.received_data,
+                latency: (&worker.latencies).into(),
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&report).expect("unable to serialize results")
+}
+
+pub struct JsonReporter {
+    pub output_file: Option<String>,
+}
+
+impl Reporter for JsonReporter {
+    fn report(&self, summary: &SummaryStatistics, run: &RunInfo) -> Result<(), String> {
+        let report = build_json_report(summary, run);
+        match &self.output_file {
+            Some(output_file) => fs::write(output_file, report)
+                .map_err(|e| format!("unable to write {output_file}: {e}")),
+            None => {
+                println!("{report}");
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_json_report {
+    use std::{
+        collections::BTreeMap,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use super::{build_json_report, SCHEMA_VERSION};
+    use crate::reporters::{RunConfig, RunInfo};
+    use crate::statistics::{
+        histogram::LatencyHistogram,
+        stats::{RequestError, SummaryStatistics, WorkerStats},
+    };
+
+    fn run_info() -> RunInfo {
+        RunInfo {
+            config: RunConfig {
+                url: "127.0.0.1:8000/".to_string(),
+                method: "GET".to_string(),
+                threads: 1,
+                connections: 10,
+                duration_sec: Some(2),
+                requests: None,
+                rate: None,
+                interval_sec: 1.0,
+                warmup_sec: 0.0,
+                stages: None,
+                timeout_sec: None,
+                keep_alive: true,
+                max_requests_per_conn: None,
+                pipeline: 1,
+                headers: vec![],
+                scenario: None,
+                thresholds: vec![],
+            },
+            started_at: UNIX_EPOCH,
+            finished_at: UNIX_EPOCH + Duration::from_secs(2),
+            threshold_results: vec![],
+        }
+    }
+
+    #[test]
+    fn test_json_report_fields() {
+        let mut latencies = LatencyHistogram::new();
+        for latency in [100, 200, 300, 400] {
+            latencies.record(latency);
+        }
+        let mut worker_stats = WorkerStats::new(Duration::from_secs(2), 4, 1, 1, 512);
+        worker_stats.set_latencies(latencies);
+        worker_stats.set_status_codes(BTreeMap::from([(200, 3), (503, 1)]));
+        worker_stats.errors_by_kind = BTreeMap::from([(RequestError::BrokenPipe, 1)]);
+        worker_stats.connect_latencies.record(150);
+        let mut summary = SummaryStatistics::new(vec![worker_stats]);
+        summary.set_dns_lookup_time(Duration::from_micros(1500));
+        let report: serde_json::Value =
+            serde_json::from_str(&build_json_report(&summary, &run_info())).unwrap();
+        assert_eq!(report["schema_version"], SCHEMA_VERSION);
+        assert_eq!(report["finished_at"], "1970-01-01T00:00:02.000Z");
+        assert_eq!(report["config"]["connections"], 10);
+        assert_eq!(report["summary"]["requests"], 4);
+        assert_eq!(report["summary"]["duration_sec"], 2.0);
+        assert_eq!(report["summary"]["requests_per_second"], 2.0);
+        assert_eq!(report["summary"]["errors"]["total"], 1);
+        assert_eq!(report["summary"]["errors"]["by_kind"]["broken_pipe"], 1);
+        assert_eq!(report["summary"]["latency"]["percentiles_us"]["p50"], 200);
+        assert_eq!(report["summary"]["latency"]["max_us"], 400);
+        assert!(report["summary"]["corrected_latency"].is_null());
+        assert_eq!(report["summary"]["phases"]["tcp_connect"]["max_us"], 150);
+        assert!(report["summary"]["phases"]["first_byte"].is_null());
+        assert!(report["summary"]["phases"]["dns_lookup"].is_null());
+        assert!(report["summary"]["tls_handshake"].is_null());
+        assert_eq!(report["summary"]["dns_lookup_us"], 1500);
+        assert!(report["warmup"].is_null());
+        assert_eq!(report["thresholds"], serde_json::json!([]));
+        assert_eq!(report["status_codes"]["503"], 1);
+        assert_eq!(report["workers"][0]["received_bytes"], 512);
+        assert_eq!(report["timeseries"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_warmup_reported_separately() {
+        let mut summary =
+            SummaryStatistics::new(vec![WorkerStats::new(Duration::from_secs(2), 40, 0, 0, 0)]);
+        summary.set_warmup(SummaryStatistics::new(vec![WorkerStats::new(
+            Duration::from_secs(1),
+            7,
+            2,
+            0,
+            0,
+        )]));
+        let report: serde_json::Value =
+            serde_json::from_str(&build_json_report(&summary, &run_info())).unwrap();
+        assert_eq!(report["summary"]["requests"], 40);
+        assert_eq!(report["summary"]["errors"]["total"], 0);
+        assert_eq!(report["warmup"]["requests"], 7);
+        assert_eq!(report["warmup"]["errors"]["total"], 2);
+    }
+}
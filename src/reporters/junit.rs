@@ -0,0 +1,120 @@
+use std::fs;
+
+use super::{Reporter, RunInfo};
+use crate::statistics::stats::SummaryStatistics;
+use crate::utils::format_timestamp;
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+pub fn build_junit_report(run: &RunInfo) -> String {
+    let results = &run.threshold_results;
+    let failures = results.iter().filter(|result| !result.passed).count();
+    let run_time = run
+        .finished_at
+        .duration_since(run.started_at)
+        .unwrap_or_default()
+        .as_secs_f64();
+    let mut report = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    report.push_str(&format!(
+        "<testsuites name=\"req_shooter\" tests=\"{}\" failures=\"{failures}\" time=\"{run_time:.3}\">\n",
+        results.len()
+    ));
+    report.push_str(&format!(
+        "  <testsuite name=\"thresholds\" tests=\"{}\" failures=\"{failures}\" errors=\"0\" \
+timestamp=\"{}\" time=\"{run_time:.3}\">\n",
+        results.len(),
+        format_timestamp(run.started_at)
+    ));
+    for result in results {
+        let name = escape_xml(&result.expression);
+        if result.passed {
+            report.push_str(&format!(
+                "    <testcase name=\"{name}\" classname=\"req_shooter.thresholds\"/>\n"
+            ));
+            continue;
+        }
+        report.push_str(&format!(
+            "    <testcase name=\"{name}\" classname=\"req_shooter.thresholds\">\n"
+        ));
+        report.push_str(&format!(
+            "      <failure type=\"threshold\" message=\"{}\"/>\n",
+            escape_xml(&format!("{} was {}", result.metric, result.actual))
+        ));
+        report.push_str("    </testcase>\n");
+    }
+    report.push_str("  </testsuite>\n</testsuites>\n");
+    report
+}
+
+pub struct JUnitReporter {
+    pub output_file: String,
+}
+
+impl Reporter for JUnitReporter {
+    fn report(&self, _summary: &SummaryStatistics, run: &RunInfo) -> Result<(), String> {
+        fs::write(&self.output_file, build_junit_report(run))
+            .map_err(|e| format!("unable to write {}: {e}", self.output_file))
+    }
+}
+
+#[cfg(test)]
+mod test_junit_report {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::build_junit_report;
+    use crate::reporters::{RunConfig, RunInfo};
+    use crate::statistics::stats::{SummaryStatistics, WorkerStats};
+    use crate::thresholds::{evaluate_thresholds, Threshold};
+
+    #[test]
+    fn test_threshold_test_cases() {
+        let summary = SummaryStatistics::new(vec![WorkerStats::new(
+            Duration::from_secs(10),
+            1000,
+            0,
+            0,
+            0,
+        )]);
+        let thresholds = vec![
+            Threshold::parse("rps>50").unwrap(),
+            Threshold::parse("rps>5000").unwrap(),
+        ];
+        let run = RunInfo {
+            config: RunConfig {
+                url: "127.0.0.1:8000/".to_string(),
+                method: "GET".to_string(),
+                threads: 1,
+                connections: 10,
+                duration_sec: Some(10),
+                requests: None,
+                rate: None,
+                interval_sec: 1.0,
+                warmup_sec: 0.0,
+                stages: None,
+                timeout_sec: None,
+                keep_alive: true,
+                max_requests_per_conn: None,
+                pipeline: 1,
+                headers: vec![],
+                scenario: None,
+                thresholds: vec![],
+            },
+            started_at: UNIX_EPOCH,
+            finished_at: UNIX_EPOCH + Duration::from_millis(10_250),
+            threshold_results: evaluate_thresholds(&thresholds, &summary),
+        };
+        let report = build_junit_report(&run);
+        assert!(report.contains("tests=\"2\" failures=\"1\" time=\"10.250\""));
+        assert!(
+            report.contains("<testcase name=\"rps&gt;50\" classname=\"req_shooter.thresholds\"/>")
+        );
+        assert!(report.contains("<failure type=\"threshold\" message=\"rps was 100.00\"/>"));
+    }
+}
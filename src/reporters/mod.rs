@@ -0,0 +1,51 @@
+use std::time::SystemTime;
+
+use serde::Serialize;
+
+use crate::statistics::stats::SummaryStatistics;
+use crate::thresholds::ThresholdResult;
+
+pub mod csv;
+pub mod json;
+pub mod junit;
+pub mod progress;
+pub mod text;
+
+pub trait Reporter {
+    fn report(&self, summary: &SummaryStatistics, run: &RunInfo) -> Result<(), String>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunConfig {
+    pub url: String,
+    pub method: String,
+    pub threads: u8,
+    pub connections: usize,
+    pub duration_sec: Option<usize>,
+    pub requests: Option<u64>,
+    pub rate: Option<u32>,
+    pub interval_sec: f64,
+    pub warmup_sec: f64,
+    pub stages: Option<Vec<String>>,
+    pub timeout_sec: Option<f64>,
+    pub keep_alive: bool,
+    pub max_requests_per_conn: Option<u64>,
+    pub pipeline: usize,
+    pub headers: Vec<String>,
+    pub scenario: Option<String>,
+    pub thresholds: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RunInfo {
+    pub config: RunConfig,
+    pub started_at: SystemTime,
+    pub finished_at: SystemTime,
+    pub threshold_results: Vec<ThresholdResult>,
+}
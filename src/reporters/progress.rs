@@ -0,0 +1,194 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{stdout, IsTerminal, Write},
+    time::Duration,
+};
+
+use crate::statistics::stats::{IntervalStats, ProgressSnapshot, WorkerMessage};
+
+struct PendingInterval {
+    missing_workers: BTreeSet<usize>,
+    stats: IntervalStats,
+    in_flight: usize,
+}
+
+// intervals are keyed by whether they are measured, so the warmup ones come first
+type IntervalKey = (bool, usize);
+
+// joins per-worker snapshots of the same interval into a single line, rewritten
+// in place on a terminal and printed line by line otherwise
+pub struct ProgressPrinter {
+    running_workers: BTreeSet<usize>,
+    interval_duration: Duration,
+    live: bool,
+    printed: bool,
+    pending: BTreeMap<IntervalKey, PendingInterval>,
+}
+
+fn format_progress_line(
+    elapsed: Duration,
+    interval_duration: Duration,
+    warmup: bool,
+    stats: &IntervalStats,
+    in_flight: usize,
+) -> String {
+    format!(
+        "[{:>6.1}s] {}requests/sec: {:<10.1} in-flight: {:<6} errors: {:<6} p99: {:.2}ms",
+        elapsed.as_secs_f64(),
+        if warmup { "(warmup) " } else { "" },
+        stats.request_count as f64 / interval_duration.as_secs_f64(),
+        in_flight,
+        stats.error_count,
+        stats.latencies.value_at_percentile(99.0) as f64 / 1000.0
+    )
+}
+
+impl ProgressPrinter {
+    pub fn new(workers: usize, interval_duration: Duration) -> ProgressPrinter {
+        ProgressPrinter {
+            running_workers: (0..workers).collect(),
+            interval_duration,
+            live: stdout().is_terminal(),
+            printed: false,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    fn collect(&mut self, snapshot: &ProgressSnapshot) -> Vec<String> {
+        let pending = self
+            .pending
+            .entry((!snapshot.warmup, snapshot.interval))
+            .or_insert_with(|| PendingInterval {
+                missing_workers: self.running_workers.clone(),
+                stats: IntervalStats::default(),
+                in_flight: 0,
+            });
+        pending.missing_workers.remove(&snapshot.worker);
+        pending.stats.merge(&snapshot.stats);
+        pending.in_flight += snapshot.in_flight;
+        self.take_complete()
+    }
+
+    // a finished worker sends no more snapshots, so the intervals only wait for the others
+    fn worker_finished(&mut self, worker: usize) -> Vec<String> {
+        self.running_workers.remove(&worker);
+        for pending in self.pending.values_mut() {
+            pending.missing_workers.remove(&worker);
+        }
+        self.take_complete()
+    }
+
+    fn take_complete(&mut self) -> Vec<String> {
+        let complete: Vec<IntervalKey> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| pending.missing_workers.is_empty())
+            .map(|(key, _)| *key)
+            .collect();
+        complete
+            .into_iter()
+            .filter_map(|key| self.pending.remove(&key).map(|pending| (key, pending)))
+            .map(|((measured, interval), pending)| {
+                format_progress_line(
+                    self.interval_duration * (interval as u32 + 1),
+                    self.interval_duration,
+                    !measured,
+                    &pending.stats,
+                    pending.in_flight,
+                )
+            })
+            .collect()
+    }
+
+    pub fn on_message(&mut self, message: &WorkerMessage) {
+        let lines = match message {
+            WorkerMessage::Progress(snapshot) => self.collect(snapshot),
+            WorkerMessage::Finished(worker, _) => self.worker_finished(*worker),
+            WorkerMessage::WarmupFinished(_) => return,
+        };
+        for line in lines {
+            self.printed = true;
+            match self.live {
+                true => {
+                    print!("\r\x1b[2K{line}");
+                    stdout().flush().ok();
+                }
+                false => println!("{line}"),
+            }
+        }
+    }
+
+    pub fn finish(&self) {
+        if self.live && self.printed {
+            println!();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_progress {
+    use std::time::Duration;
+
+    use super::ProgressPrinter;
+    use crate::statistics::stats::{IntervalStats, ProgressSnapshot};
+
+    fn snapshot(
+        worker: usize,
+        interval: usize,
+        request_count: u32,
+        in_flight: usize,
+    ) -> ProgressSnapshot {
+        ProgressSnapshot {
+            worker,
+            warmup: false,
+            interval,
+            stats: IntervalStats {
+                request_count,
+                ..Default::default()
+            },
+            in_flight,
+        }
+    }
+
+    #[test]
+    fn test_line_printed_once_all_workers_reported() {
+        let mut printer = ProgressPrinter::new(2, Duration::from_millis(500));
+        assert!(printer.collect(&snapshot(0, 0, 100, 10)).is_empty());
+        assert!(printer.collect(&snapshot(0, 1, 100, 10)).is_empty());
+        let lines = printer.collect(&snapshot(1, 0, 150, 5));
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with("[   0.5s] requests/sec: 500.0 "));
+        assert!(lines[0].contains("in-flight: 15 "));
+        assert_eq!(printer.collect(&snapshot(1, 1, 1, 1)).len(), 1);
+        assert!(printer.pending.is_empty());
+    }
+
+    #[test]
+    fn test_finished_worker_not_waited_for() {
+        let mut printer = ProgressPrinter::new(2, Duration::from_secs(1));
+        assert!(printer.collect(&snapshot(0, 0, 100, 1)).is_empty());
+        assert_eq!(printer.collect(&snapshot(1, 0, 100, 1)).len(), 1);
+        // worker 0 stops after its first interval, worker 1 has already reported the next two
+        assert!(printer.collect(&snapshot(1, 1, 50, 1)).is_empty());
+        assert!(printer.collect(&snapshot(1, 2, 50, 1)).is_empty());
+        let lines = printer.worker_finished(0);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("[   2.0s] requests/sec: 50.0 "));
+        assert!(lines[1].starts_with("[   3.0s] "));
+        assert_eq!(printer.collect(&snapshot(1, 3, 10, 1)).len(), 1);
+        assert!(printer.pending.is_empty());
+    }
+
+    #[test]
+    fn test_warmup_intervals_marked() {
+        let mut printer = ProgressPrinter::new(1, Duration::from_secs(1));
+        let warmup = ProgressSnapshot {
+            warmup: true,
+            ..snapshot(0, 0, 10, 1)
+        };
+        let lines = printer.collect(&warmup);
+        assert!(lines[0].starts_with("[   1.0s] (warmup) requests/sec: 10.0 "));
+        let lines = printer.collect(&snapshot(0, 0, 10, 1));
+        assert!(lines[0].starts_with("[   1.0s] requests/sec: 10.0 "));
+    }
+}
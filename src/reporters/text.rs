@@ -0,0 +1,44 @@
+use colored::Colorize;
+
+use super::{Reporter, RunInfo};
+use crate::statistics::stats::SummaryStatistics;
+use crate::thresholds::ThresholdResult;
+
+fn print_threshold_results(results: &[ThresholdResult]) {
+    println!();
+    println!("{}", "Thresholds:".cyan().bold().underline());
+    for result in results {
+        let line = format!(
+            "{}\t\t\t {} was {}",
+            result.expression, result.metric, result.actual
+        );
+        match result.passed {
+            true => println!("\t{} {}", "\u{2713}".green(), line.green()),
+            false => println!("\t{} {}", "\u{2717}".red(), line.red()),
+        }
+    }
+}
+
+pub struct TextReporter {
+    pub show_warmup: bool,
+}
+
+impl Reporter for TextReporter {
+    fn report(&self, summary: &SummaryStatistics, run: &RunInfo) -> Result<(), String> {
+        if let Some(warmup) = summary.warmup.as_ref().filter(|_| self.show_warmup) {
+            println!();
+            println!(
+                "{}",
+                "Warmup (excluded from the results below):".yellow().bold()
+            );
+            warmup.represent();
+            println!();
+            println!("{}", "Measured run:".yellow().bold());
+        }
+        summary.represent();
+        if !run.threshold_results.is_empty() {
+            print_threshold_results(&run.threshold_results);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use super::template::{CsvTable, RenderContext, Template, XorShiftRng};
+use crate::scenario::Endpoint;
+use crate::url_parser::ParsedUrlHeader;
+
+pub trait RequestGenerator: CloneGenerator {
+    // writes the next request into the buffer and returns the index of its endpoint
+    fn next_request(&mut self, buffer: &mut Vec<u8>) -> usize;
+}
+
+pub trait CloneGenerator {
+    fn clone_generator(&self) -> Box<dyn RequestGenerator + Send + Sync>;
+}
+
+impl Clone for Box<dyn RequestGenerator + Send + Sync> {
+    fn clone(&self) -> Self {
+        self.clone_generator()
+    }
+}
+
+#[derive(Clone)]
+pub struct StaticRequestGenerator {
+    request: Vec<u8>,
+}
+
+impl StaticRequestGenerator {
+    pub fn new(request: Vec<u8>) -> StaticRequestGenerator {
+        StaticRequestGenerator { request }
+    }
+}
+
+impl CloneGenerator for StaticRequestGenerator {
+    fn clone_generator(&self) -> Box<dyn RequestGenerator + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+impl RequestGenerator for StaticRequestGenerator {
+    fn next_request(&mut self, buffer: &mut Vec<u8>) -> usize {
+        buffer.extend_from_slice(&self.request);
+        0
+    }
+}
+
+pub struct TemplateRequestGenerator {
+    parsed_url: ParsedUrlHeader,
+    head: Template,
+    body: Option<Template>,
+    csv: Option<Arc<CsvTable>>,
+    sequence: Arc<AtomicU64>,
+    rng: XorShiftRng,
+    body_buffer: Vec<u8>,
+}
+
+impl Clone for TemplateRequestGenerator {
+    // every worker gets its own random stream while sharing the sequence
+    fn clone(&self) -> Self {
+        TemplateRequestGenerator {
+            parsed_url: self.parsed_url.clone(),
+            head: self.head.clone(),
+            body: self.body.clone(),
+            csv: self.csv.clone(),
+            sequence: self.sequence.clone(),
+            rng: XorShiftRng::from_entropy(),
+            body_buffer: vec![],
+        }
+    }
+}
+
+impl CloneGenerator for TemplateRequestGenerator {
+    fn clone_generator(&self) -> Box<dyn RequestGenerator + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+impl RequestGenerator for TemplateRequestGenerator {
+    fn next_request(&mut self, buffer: &mut Vec<u8>) -> usize {
+        let mut context = RenderContext {
+            seq: self.sequence.fetch_add(1, Ordering::Relaxed),
+            rng: &mut self.rng,
+            csv: self.csv.as_deref(),
+        };
+        self.head.render(&mut context, buffer);
+        let Some(body) = &self.body else {
+            self.parsed_url.finish_request(buffer, None);
+            return 0;
+        };
+        self.body_buffer.clear();
+        body.render(&mut context, &mut self.body_buffer);
+        self.parsed_url
+            .finish_request(buffer, Some(&self.body_buffer));
+        0
+    }
+}
+
+pub struct WeightedRequestGenerator {
+    generators: Vec<Box<dyn RequestGenerator + Send + Sync>>,
+    cumulative_weights: Vec<u64>,
+    rng: XorShiftRng,
+}
+
+impl Clone for WeightedRequestGenerator {
+    fn clone(&self) -> Self {
+        WeightedRequestGenerator {
+            generators: self.generators.clone(),
+            cumulative_weights: self.cumulative_weights.clone(),
+            rng: XorShiftRng::from_entropy(),
+        }
+    }
+}
+
+impl CloneGenerator for WeightedRequestGenerator {
+    fn clone_generator(&self) -> Box<dyn RequestGenerator + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+impl RequestGenerator for WeightedRequestGenerator {
+    fn next_request(&mut self, buffer: &mut Vec<u8>) -> usize {
+        let total_weight = self.cumulative_weights.last().copied().unwrap_or(1);
+        let point = self.rng.next_u64() % total_weight;
+        let endpoint = self
+            .cumulative_weights
+            .partition_point(|weight| *weight <= point);
+        self.generators[endpoint].next_request(buffer);
+        endpoint
+    }
+}
+
+pub fn create_request_generator(
+    parsed_url: &ParsedUrlHeader,
+    template_body: bool,
+    csv: Option<&Arc<CsvTable>>,
+) -> Result<Box<dyn RequestGenerator + Send + Sync>, String> {
+    let head = Template::parse(
+        parsed_url.compile_request_head().as_bytes(),
+        csv.map(|csv| csv.as_ref()),
+    )?;
+    let body = match &parsed_url.body {
+        Some(body) if template_body => Some(Template::parse(body, csv.map(|csv| csv.as_ref()))?),
+        Some(body) => Some(Template::literal(body)),
+        None => None,
+    };
+    if head.is_static() && body.as_ref().is_none_or(Template::is_static) {
+        let request = parsed_url.clone().compile_request();
+        return Ok(Box::new(StaticRequestGenerator::new(request)));
+    }
+    Ok(Box::new(TemplateRequestGenerator {
+        parsed_url: parsed_url.clone(),
+        head,
+        body,
+        csv: csv.cloned(),
+        sequence: Arc::new(AtomicU64::new(0)),
+        rng: XorShiftRng::from_entropy(),
+        body_buffer: vec![],
+    }))
+}
+
+pub fn create_scenario_generator(
+    endpoints: &[Endpoint],
+    csv: Option<&Arc<CsvTable>>,
+) -> Result<Box<dyn RequestGenerator + Send + Sync>, String> {
+    let mut generators = vec![];
+    let mut cumulative_weights = vec![];
+    let mut total_weight = 0;
+    for endpoint in endpoints {
+        let generator = create_request_generator(&endpoint.parsed_url, true, csv)
+            .map_err(|e| format!("endpoint \"{}\": {e}", endpoint.name))?;
+        generators.push(generator);
+        total_weight += endpoint.weight as u64;
+        cumulative_weights.push(total_weight);
+    }
+    Ok(Box::new(WeightedRequestGenerator {
+        generators,
+        cumulative_weights,
+        rng: XorShiftRng::from_entropy(),
+    }))
+}
+
+#[cfg(test)]
+mod test_generator {
+    use super::{create_request_generator, create_scenario_generator};
+    use crate::scenario::parse_scenario;
+    use crate::url_parser::ParsedUrlHeader;
+
+    fn next_request(generator: &mut Box<dyn super::RequestGenerator + Send + Sync>) -> String {
+        let mut buffer = vec![];
+        generator.next_request(&mut buffer);
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_static_request() {
+        let mut parsed_url = ParsedUrlHeader::parse_url("localhost:8000/items").unwrap();
+        let mut generator = create_request_generator(&parsed_url, true, None).unwrap();
+        let expected = String::from_utf8(parsed_url.compile_request()).unwrap();
+        assert_eq!(next_request(&mut generator), expected);
+        assert_eq!(next_request(&mut generator), expected);
+    }
+
+    #[test]
+    fn test_sequence_shared_between_clones() {
+        let parsed_url = ParsedUrlHeader::parse_url("localhost:8000/users/{{seq}}").unwrap();
+        let mut generator = create_request_generator(&parsed_url, true, None).unwrap();
+        let mut cloned = generator.clone();
+        assert!(next_request(&mut generator).starts_with("GET /users/0 HTTP/1.1\r\n"));
+        assert!(next_request(&mut cloned).starts_with("GET /users/1 HTTP/1.1\r\n"));
+        assert!(next_request(&mut generator).starts_with("GET /users/2 HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn test_templated_body_content_length() {
+        let mut parsed_url = ParsedUrlHeader::parse_url("localhost:8000/orders").unwrap();
+        parsed_url.set_method("POST").unwrap();
+        parsed_url
+            .add_header("Idempotency-Key: {{uuid}}".to_string())
+            .unwrap();
+        parsed_url.set_body(b"{\"id\":{{seq:1000}}}".to_vec());
+        let mut generator = create_request_generator(&parsed_url, true, None).unwrap();
+        let request = next_request(&mut generator);
+        assert!(!request.contains("{{"));
+        assert!(request.contains("Content-Length: 11\r\n"));
+        assert!(request.ends_with("\r\n\r\n{\"id\":1000}"));
+    }
+
+    #[test]
+    fn test_binary_body_is_not_templated() {
+        let mut parsed_url = ParsedUrlHeader::parse_url("localhost:8000/upload").unwrap();
+        parsed_url.set_method("POST").unwrap();
+        parsed_url.set_body(b"{{seq}}".to_vec());
+        let mut generator = create_request_generator(&parsed_url, false, None).unwrap();
+        assert!(next_request(&mut generator).ends_with("Content-Length: 7\r\n\r\n{{seq}}"));
+    }
+
+    #[test]
+    fn test_weighted_endpoints() {
+        let base_url = ParsedUrlHeader::parse_url("localhost:8000/").unwrap();
+        let content = r#"{"endpoints": [
+            {"weight": 3, "path": "/items"},
+            {"weight": 1, "method": "POST", "path": "/orders", "body": "{{seq}}"}
+        ]}"#;
+        let endpoints = parse_scenario(content, &base_url).unwrap();
+        let mut generator = create_scenario_generator(&endpoints, None).unwrap();
+        let mut picked = [0; 2];
+        for _ in 0..4000 {
+            let mut buffer = vec![];
+            let endpoint = generator.next_request(&mut buffer);
+            let expected = ["GET /items ", "POST /orders "][endpoint];
+            assert!(buffer.starts_with(expected.as_bytes()));
+            picked[endpoint] += 1;
+        }
+        assert!((2700..3300).contains(&picked[0]));
+    }
+}
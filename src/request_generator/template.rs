@@ -0,0 +1,333 @@
+use std::{
+    collections::hash_map::RandomState,
+    fs,
+    hash::{BuildHasher, Hasher},
+};
+
+const RANDSTR_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+#[derive(Debug, Clone)]
+pub struct XorShiftRng {
+    state: u64,
+}
+
+impl XorShiftRng {
+    pub fn new(seed: u64) -> XorShiftRng {
+        XorShiftRng { state: seed.max(1) }
+    }
+
+    pub fn from_entropy() -> XorShiftRng {
+        XorShiftRng::new(RandomState::new().build_hasher().finish())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvTable {
+    columns: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+fn parse_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            char if char.is_control() => {
+                return Err("control characters are not allowed".to_string())
+            }
+            char => field.push(char),
+        }
+    }
+    if quoted {
+        return Err("unterminated quoted field".to_string());
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+impl CsvTable {
+    pub fn load(path: &str) -> Result<CsvTable, String> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("unable to read csv file {path}: {e}"))?;
+        CsvTable::parse(&content).map_err(|e| format!("invalid csv file {path}: {e}"))
+    }
+
+    pub fn parse(content: &str) -> Result<CsvTable, String> {
+        let mut lines = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let Some((_, header)) = lines.next() else {
+            return Err("header row is missing".to_string());
+        };
+        let columns: Vec<String> = parse_csv_line(header)
+            .map_err(|e| format!("line 1: {e}"))?
+            .into_iter()
+            .map(|column| column.trim().to_string())
+            .collect();
+        let mut rows = vec![];
+        for (index, line) in lines {
+            let row = parse_csv_line(line).map_err(|e| format!("line {}: {e}", index + 1))?;
+            if row.len() != columns.len() {
+                return Err(format!(
+                    "line {}: expected {} fields, found {}",
+                    index + 1,
+                    columns.len(),
+                    row.len()
+                ));
+            }
+            rows.push(row);
+        }
+        if rows.is_empty() {
+            return Err("no data rows".to_string());
+        }
+        Ok(CsvTable { columns, rows })
+    }
+
+    fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column == name)
+    }
+
+    fn value(&self, row: u64, column: usize) -> &str {
+        &self.rows[(row % self.rows.len() as u64) as usize][column]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Placeholder {
+    Seq(u64),
+    RandInt(i64, i64),
+    RandStr(usize),
+    Uuid,
+    Csv(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(Vec<u8>),
+    Placeholder(Placeholder),
+}
+
+pub struct RenderContext<'a> {
+    pub seq: u64,
+    pub rng: &'a mut XorShiftRng,
+    pub csv: Option<&'a CsvTable>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+fn parse_argument<T: std::str::FromStr>(expression: &str, argument: &str) -> Result<T, String> {
+    argument
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid argument {argument:?} in {{{{{expression}}}}}"))
+}
+
+fn parse_placeholder(expression: &str, csv: Option<&CsvTable>) -> Result<Placeholder, String> {
+    let arguments: Vec<&str> = expression.split(':').collect();
+    match arguments.as_slice() {
+        ["seq"] => Ok(Placeholder::Seq(0)),
+        ["seq", start] => Ok(Placeholder::Seq(parse_argument(expression, start)?)),
+        ["randint", min, max] => {
+            let min = parse_argument(expression, min)?;
+            let max = parse_argument(expression, max)?;
+            match min <= max {
+                true => Ok(Placeholder::RandInt(min, max)),
+                false => Err(format!("empty range in {{{{{expression}}}}}")),
+            }
+        }
+        ["randstr", length] => Ok(Placeholder::RandStr(parse_argument(expression, length)?)),
+        ["uuid"] => Ok(Placeholder::Uuid),
+        ["csv", column] => {
+            let csv = csv.ok_or(format!("{{{{{expression}}}}} requires a csv file"))?;
+            csv.column_index(column.trim())
+                .map(Placeholder::Csv)
+                .ok_or(format!("csv file has no column {:?}", column.trim()))
+        }
+        _ => Err(format!("unknown placeholder {{{{{expression}}}}}")),
+    }
+}
+
+fn render_uuid(rng: &mut XorShiftRng, output: &mut Vec<u8>) {
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&rng.next_u64().to_be_bytes());
+    bytes[8..].copy_from_slice(&rng.next_u64().to_be_bytes());
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    for (index, byte) in bytes.iter().enumerate() {
+        if [4, 6, 8, 10].contains(&index) {
+            output.push(b'-');
+        }
+        output.extend_from_slice(format!("{byte:02x}").as_bytes());
+    }
+}
+
+impl Template {
+    pub fn literal(source: &[u8]) -> Template {
+        Template {
+            segments: vec![Segment::Literal(source.to_vec())],
+        }
+    }
+
+    pub fn parse(source: &[u8], csv: Option<&CsvTable>) -> Result<Template, String> {
+        let mut segments = vec![];
+        let mut rest = source;
+        while let Some(start) = rest.windows(2).position(|window| window == b"{{") {
+            let Some(length) = rest[start + 2..].windows(2).position(|w| w == b"}}") else {
+                return Err("unterminated placeholder, expected }}".to_string());
+            };
+            let expression = std::str::from_utf8(&rest[start + 2..start + 2 + length])
+                .map_err(|_| "placeholder is not valid utf-8".to_string())?;
+            if start > 0 {
+                segments.push(Segment::Literal(rest[..start].to_vec()));
+            }
+            segments.push(Segment::Placeholder(parse_placeholder(
+                expression.trim(),
+                csv,
+            )?));
+            rest = &rest[start + 2 + length + 2..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_vec()));
+        }
+        Ok(Template { segments })
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.segments
+            .iter()
+            .all(|segment| matches!(segment, Segment::Literal(_)))
+    }
+
+    pub fn render(&self, context: &mut RenderContext, output: &mut Vec<u8>) {
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => output.extend_from_slice(literal),
+                Segment::Placeholder(Placeholder::Seq(start)) => {
+                    output.extend_from_slice((start + context.seq).to_string().as_bytes())
+                }
+                Segment::Placeholder(Placeholder::RandInt(min, max)) => {
+                    let range = (*max as i128 - *min as i128 + 1) as u128;
+                    let value = *min as i128 + (context.rng.next_u64() as u128 % range) as i128;
+                    output.extend_from_slice(value.to_string().as_bytes());
+                }
+                Segment::Placeholder(Placeholder::RandStr(length)) => {
+                    for _ in 0..*length {
+                        let index = context.rng.next_u64() % RANDSTR_ALPHABET.len() as u64;
+                        output.push(RANDSTR_ALPHABET[index as usize]);
+                    }
+                }
+                Segment::Placeholder(Placeholder::Uuid) => render_uuid(context.rng, output),
+                Segment::Placeholder(Placeholder::Csv(column)) => {
+                    if let Some(csv) = context.csv {
+                        output.extend_from_slice(csv.value(context.seq, *column).as_bytes());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_template {
+    use super::{CsvTable, RenderContext, Template, XorShiftRng};
+
+    fn render(template: &Template, seq: u64, csv: Option<&CsvTable>) -> String {
+        let mut rng = XorShiftRng::new(42);
+        let mut context = RenderContext {
+            seq,
+            rng: &mut rng,
+            csv,
+        };
+        let mut output = vec![];
+        template.render(&mut context, &mut output);
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_literal_only() {
+        let template = Template::parse(b"GET /items HTTP/1.1", None).unwrap();
+        assert!(template.is_static());
+        assert_eq!(render(&template, 7, None), "GET /items HTTP/1.1");
+    }
+
+    #[test]
+    fn test_sequence() {
+        let template = Template::parse(b"/users/{{seq}}?page={{ seq:100 }}", None).unwrap();
+        assert!(!template.is_static());
+        assert_eq!(render(&template, 5, None), "/users/5?page=105");
+    }
+
+    #[test]
+    fn test_random_values() {
+        let template = Template::parse(b"{{randint:-3:3}}", None).unwrap();
+        for seq in 0..100 {
+            let value: i64 = render(&template, seq, None).parse().unwrap();
+            assert!((-3..=3).contains(&value));
+        }
+        let template = Template::parse(b"{{randstr:12}}", None).unwrap();
+        let value = render(&template, 0, None);
+        assert_eq!(value.len(), 12);
+        assert!(value.chars().all(|char| char.is_ascii_alphanumeric()));
+    }
+
+    #[test]
+    fn test_uuid() {
+        let template = Template::parse(b"{{uuid}}", None).unwrap();
+        let value = render(&template, 0, None);
+        let groups: Vec<&str> = value.split('-').collect();
+        assert_eq!(
+            groups.iter().map(|g| g.len()).collect::<Vec<_>>(),
+            [8, 4, 4, 4, 12]
+        );
+        assert!(groups[2].starts_with('4'));
+        assert!("89ab".contains(&groups[3][..1]));
+    }
+
+    #[test]
+    fn test_csv_values() {
+        let csv = CsvTable::parse("id,name\n1,\"Doe, John\"\n2,\"say \"\"hi\"\"\"\n").unwrap();
+        let template = Template::parse(b"{{csv:id}}={{csv:name}}", Some(&csv)).unwrap();
+        assert_eq!(render(&template, 0, Some(&csv)), "1=Doe, John");
+        assert_eq!(render(&template, 1, Some(&csv)), "2=say \"hi\"");
+        assert_eq!(render(&template, 2, Some(&csv)), "1=Doe, John");
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(Template::parse(b"{{seq", None).is_err());
+        assert!(Template::parse(b"{{unknown}}", None).is_err());
+        assert!(Template::parse(b"{{randint:5:1}}", None).is_err());
+        assert!(Template::parse(b"{{randstr:abc}}", None).is_err());
+        assert!(Template::parse(b"{{csv:id}}", None).is_err());
+        let csv = CsvTable::parse("id\n1\n").unwrap();
+        assert!(Template::parse(b"{{csv:name}}", Some(&csv)).is_err());
+    }
+
+    #[test]
+    fn test_invalid_csv() {
+        assert!(CsvTable::parse("").is_err());
+        assert!(CsvTable::parse("id,name\n").is_err());
+        assert!(CsvTable::parse("id,name\n1\n").is_err());
+        assert!(CsvTable::parse("id\n\"1\n").is_err());
+    }
+}
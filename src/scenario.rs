@@ -0,0 +1,141 @@
+use std::fs;
+
+use serde::Deserialize;
+
+use crate::url_parser::ParsedUrlHeader;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScenarioFile {
+    endpoints: Vec<EndpointConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EndpointConfig {
+    name: Option<String>,
+    #[serde(default = "default_weight")]
+    weight: u32,
+    #[serde(default = "default_method")]
+    method: String,
+    path: String,
+    #[serde(default)]
+    headers: Vec<String>,
+    body: Option<String>,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+#[derive(Debug, Clone)]
+pub struct Endpoint {
+    pub name: String,
+    pub weight: u32,
+    pub parsed_url: ParsedUrlHeader,
+}
+
+impl EndpointConfig {
+    fn into_endpoint(self, base_url: &ParsedUrlHeader) -> Result<Endpoint, String> {
+        let name = self
+            .name
+            .unwrap_or(format!("{} {}", self.method.to_uppercase(), self.path));
+        if self.weight == 0 {
+            return Err(format!("endpoint \"{name}\" must have a positive weight"));
+        }
+        if !self.path.starts_with('/') {
+            return Err(format!("path of endpoint \"{name}\" must start with /"));
+        }
+        let mut parsed_url = base_url.clone();
+        parsed_url.resource = self.path;
+        parsed_url.set_method(&self.method)?;
+        for header in self.headers {
+            parsed_url
+                .add_header(header)
+                .map_err(|e| format!("endpoint \"{name}\": {e}"))?;
+        }
+        if let Some(body) = self.body {
+            parsed_url.set_body(body.into_bytes());
+        }
+        Ok(Endpoint {
+            name,
+            weight: self.weight,
+            parsed_url,
+        })
+    }
+}
+
+pub fn parse_scenario(content: &str, base_url: &ParsedUrlHeader) -> Result<Vec<Endpoint>, String> {
+    let scenario: ScenarioFile = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    if scenario.endpoints.is_empty() {
+        return Err("no endpoints defined".to_string());
+    }
+    scenario
+        .endpoints
+        .into_iter()
+        .map(|endpoint| endpoint.into_endpoint(base_url))
+        .collect()
+}
+
+pub fn load_scenario(path: &str, base_url: &ParsedUrlHeader) -> Result<Vec<Endpoint>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("unable to read scenario {path}: {e}"))?;
+    parse_scenario(&content, base_url).map_err(|e| format!("invalid scenario {path}: {e}"))
+}
+
+#[cfg(test)]
+mod test_scenario {
+    use super::parse_scenario;
+    use crate::url_parser::ParsedUrlHeader;
+
+    fn base_url() -> ParsedUrlHeader {
+        let mut parsed_url = ParsedUrlHeader::parse_url("localhost:8000/").unwrap();
+        parsed_url
+            .add_header("Authorization: Bearer token".to_string())
+            .unwrap();
+        parsed_url
+    }
+
+    #[test]
+    fn test_parse_endpoints() {
+        let content = r#"{"endpoints": [
+            {"weight": 70, "path": "/items"},
+            {"name": "create order", "weight": 10, "method": "post", "path": "/orders",
+             "headers": ["Content-Type: application/json"], "body": "{}"}
+        ]}"#;
+        let endpoints = parse_scenario(content, &base_url()).unwrap();
+        assert_eq!(endpoints.len(), 2);
+        assert_eq!(endpoints[0].name, "GET /items");
+        assert_eq!(endpoints[0].weight, 70);
+        assert_eq!(endpoints[0].parsed_url.resource, "/items");
+        assert_eq!(endpoints[1].name, "create order");
+        assert_eq!(endpoints[1].parsed_url.method, "POST");
+        assert_eq!(endpoints[1].parsed_url.headers.len(), 2);
+        assert_eq!(endpoints[1].parsed_url.body, Some(b"{}".to_vec()));
+    }
+
+    #[test]
+    fn test_invalid_scenarios() {
+        let base_url = base_url();
+        assert!(parse_scenario(r#"{"endpoints": []}"#, &base_url).is_err());
+        assert!(parse_scenario(r#"{"endpoints": [{"path": "items"}]}"#, &base_url).is_err());
+        assert!(
+            parse_scenario(r#"{"endpoints": [{"path": "/", "weight": 0}]}"#, &base_url).is_err()
+        );
+        assert!(parse_scenario(
+            r#"{"endpoints": [{"path": "/", "method": "G T"}]}"#,
+            &base_url
+        )
+        .is_err());
+        assert!(parse_scenario(
+            r#"{"endpoints": [{"path": "/", "headers": ["bad"]}]}"#,
+            &base_url
+        )
+        .is_err());
+        assert!(parse_scenario(r#"{"endpoints": [{"url": "/"}]}"#, &base_url).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use std::time::Duration;
+
+use crate::utils::parse_duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StageUnit {
+    Rate,
+    Connections,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stage {
+    pub name: String,
+    pub duration: Duration,
+    pub from: f64,
+    pub to: f64,
+}
+
+impl Stage {
+    fn value_at(&self, offset: Duration) -> f64 {
+        let progress = offset.as_secs_f64() / self.duration.as_secs_f64();
+        self.from + (self.to - self.from) * progress.min(1.0)
+    }
+
+    fn total_requests(&self) -> f64 {
+        (self.from + self.to) / 2.0 * self.duration.as_secs_f64()
+    }
+
+    // time after the stage start when `count` requests have been scheduled at the ramping rate
+    fn time_of_request(&self, count: f64) -> f64 {
+        if count <= 0.0 {
+            return 0.0;
+        }
+        let acceleration = (self.to - self.from) / self.duration.as_secs_f64();
+        let root = (self.from * self.from + 2.0 * acceleration * count)
+            .max(0.0)
+            .sqrt();
+        2.0 * count / (self.from + root)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StagePlan {
+    pub unit: StageUnit,
+    pub stages: Vec<Stage>,
+}
+
+fn parse_target(target: &str) -> Result<(f64, Option<StageUnit>), String> {
+    let (value, unit) = match target {
+        target if target.ends_with("rps") => (&target[..target.len() - 3], Some(StageUnit::Rate)),
+        target if target.ends_with('c') => {
+            (&target[..target.len() - 1], Some(StageUnit::Connections))
+        }
+        target => (target, None),
+    };
+    match value.trim().parse::<f64>() {
+        Ok(value) if value >= 0.0 && value.is_finite() => Ok((value, unit)),
+        _ => Err(format!("invalid stage target: {target}")),
+    }
+}
+
+// a stage either holds its target or ramps linearly between two targets, such as 0->100rps
+fn parse_targets(targets: &str) -> Result<(f64, f64, Option<StageUnit>), String> {
+    let Some((from, to)) = targets.split_once("->") else {
+        let (target, unit) = parse_target(targets)?;
+        return Ok((target, target, unit));
+    };
+    let (from, from_unit) = parse_target(from.trim())?;
+    let (to, to_unit) = parse_target(to.trim())?;
+    match (from_unit, to_unit) {
+        (Some(from_unit), Some(to_unit)) if from_unit != to_unit => Err(format!(
+            "stage ramp \"{targets}\" can not mix rps and connection targets"
+        )),
+        (from_unit, to_unit) => Ok((from, to, to_unit.or(from_unit))),
+    }
+}
+
+impl StagePlan {
+    pub fn parse(spec: &str) -> Result<StagePlan, String> {
+        let mut unit = None;
+        let mut stages = vec![];
+        for stage in spec.split(',').map(str::trim) {
+            let Some((duration, targets)) = stage.split_once('@') else {
+                return Err(format!(
+                    "stage \"{stage}\" must be in \"duration@target\" or \"duration@from->to\" form"
+                ));
+            };
+            let duration = parse_duration(duration)?;
+            if duration.is_zero() {
+                return Err(format!("stage \"{stage}\" must have a positive duration"));
+            }
+            let (from, to, stage_unit) = parse_targets(targets.trim())?;
+            match (unit, stage_unit) {
+                (Some(unit), Some(stage_unit)) if unit != stage_unit => {
+                    return Err("stages can not mix rps and connection targets".to_string())
+                }
+                (None, stage_unit) => unit = stage_unit,
+                _ => {}
+            }
+            stages.push(Stage {
+                name: stage.to_string(),
+                duration,
+                from,
+                to,
+            });
+        }
+        let Some(unit) = unit else {
+            return Err("stage targets need a unit, such as 100rps or 50c".to_string());
+        };
+        Ok(StagePlan { unit, stages })
+    }
+
+    pub fn constant_rate(rate: f64, duration: Duration) -> StagePlan {
+        StagePlan {
+            unit: StageUnit::Rate,
+            stages: vec![Stage {
+                name: format!("{}s@{rate}rps", duration.as_secs_f64()),
+                duration,
+                from: rate,
+                to: rate,
+            }],
+        }
+    }
+
+    // rates are shared between the workers, connection counts are per worker like -c
+    pub fn per_worker(&self, workers: usize) -> StagePlan {
+        let mut plan = self.clone();
+        if plan.unit == StageUnit::Rate {
+            for stage in &mut plan.stages {
+                stage.from /= workers as f64;
+                stage.to /= workers as f64;
+            }
+        }
+        plan
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.stages.iter().map(|stage| stage.duration).sum()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.stages.iter().map(|stage| stage.name.clone()).collect()
+    }
+
+    pub fn max_target(&self) -> f64 {
+        self.stages.iter().map(|stage| stage.to).fold(0.0, f64::max)
+    }
+
+    pub fn stage_at(&self, elapsed: Duration) -> Option<usize> {
+        let mut stage_end = Duration::ZERO;
+        for (index, stage) in self.stages.iter().enumerate() {
+            stage_end += stage.duration;
+            if elapsed < stage_end {
+                return Some(index);
+            }
+        }
+        None
+    }
+
+    pub fn target_at(&self, elapsed: Duration) -> f64 {
+        let mut stage_start = Duration::ZERO;
+        for stage in &self.stages {
+            if elapsed < stage_start + stage.duration {
+                return stage.value_at(elapsed - stage_start);
+            }
+            stage_start += stage.duration;
+        }
+        self.stages.last().map_or(0.0, |stage| stage.to)
+    }
+
+    // offset from the start of the run at which the request with this index is due
+    pub fn request_time(&self, index: u64) -> Option<Duration> {
+        let mut remaining = index as f64;
+        let mut stage_start = Duration::ZERO;
+        for stage in &self.stages {
+            let stage_requests = stage.total_requests();
+            if remaining < stage_requests {
+                let offset = Duration::from_secs_f64(stage.time_of_request(remaining));
+                return Some(stage_start + offset.min(stage.duration));
+            }
+            remaining -= stage_requests;
+            stage_start += stage.duration;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test_stages {
+    use std::time::Duration;
+
+    use super::{StagePlan, StageUnit};
+
+    #[test]
+    fn test_parse_stages() {
+        let plan = StagePlan::parse("30s@100rps, 2m@100->1000rps, 30s@1000->0").unwrap();
+        assert_eq!(plan.unit, StageUnit::Rate);
+        assert_eq!(
+            plan.names(),
+            ["30s@100rps", "2m@100->1000rps", "30s@1000->0"]
+        );
+        assert_eq!(plan.duration(), Duration::from_secs(180));
+        assert_eq!(plan.stages[0].from, 100.0);
+        assert_eq!(plan.stages[1].from, 100.0);
+        assert_eq!(plan.stages[1].to, 1000.0);
+        assert_eq!(plan.stages[2].to, 0.0);
+        let plan = StagePlan::parse("10s@20c,10s@20c").unwrap();
+        assert_eq!(plan.unit, StageUnit::Connections);
+        assert!(StagePlan::parse("30s@100").is_err());
+        assert!(StagePlan::parse("30s@100rps, 10s@5c").is_err());
+        assert!(StagePlan::parse("0s@100rps").is_err());
+        assert!(StagePlan::parse("30s").is_err());
+        assert!(StagePlan::parse("30s@-1rps").is_err());
+        assert!(StagePlan::parse("30s@0c->100rps").is_err());
+        assert!(StagePlan::parse("30s@0->").is_err());
+    }
+
+    #[test]
+    fn test_steps() {
+        let plan = StagePlan::parse("10s@20c, 10s@50c").unwrap();
+        assert_eq!(plan.target_at(Duration::from_secs(5)), 20.0);
+        assert_eq!(plan.target_at(Duration::from_secs(10)), 50.0);
+        let plan = StagePlan::parse("10s@10rps, 10s@20rps").unwrap();
+        assert_eq!(plan.request_time(100), Some(Duration::from_secs(10)));
+        assert_eq!(plan.request_time(110), Some(Duration::from_millis(10_500)));
+    }
+
+    #[test]
+    fn test_linear_ramp() {
+        let plan = StagePlan::parse("10s@0->100c, 10s@100->0c").unwrap();
+        assert_eq!(plan.target_at(Duration::from_secs(5)), 50.0);
+        assert_eq!(plan.target_at(Duration::from_secs(15)), 50.0);
+        assert_eq!(plan.target_at(Duration::from_secs(30)), 0.0);
+        assert_eq!(plan.stage_at(Duration::from_secs(10)), Some(1));
+        assert_eq!(plan.stage_at(Duration::from_secs(20)), None);
+    }
+
+    #[test]
+    fn test_request_times() {
+        let plan = StagePlan::constant_rate(10.0, Duration::from_secs(2));
+        assert_eq!(plan.request_time(0), Some(Duration::ZERO));
+        assert_eq!(plan.request_time(15), Some(Duration::from_millis(1500)));
+        assert_eq!(plan.request_time(20), None);
+        // 0 -> 100rps over 10s schedules 500 requests, half of them in the last 2.93s
+        let plan = StagePlan::parse("10s@0->100rps, 5s@100->0rps").unwrap();
+        let time = plan.request_time(250).unwrap().as_secs_f64();
+        assert!((time - 50.0_f64.sqrt()).abs() < 1e-6);
+        let time = plan.request_time(500).unwrap().as_secs_f64();
+        assert!((time - 10.0).abs() < 1e-6);
+        assert!(plan.request_time(750).is_none());
+        let plan = plan.per_worker(2);
+        assert_eq!(plan.stages[0].to, 50.0);
+    }
+}
@@ -0,0 +1,179 @@
+// Log-linear bucketed histogram in the spirit of HdrHistogram: values below
+// SUB_BUCKET_COUNT are stored exactly, larger values share a bucket with
+// neighbours that are within 1 / SUB_BUCKET_HALF (~0.4%) of each other.
+const SUB_BUCKET_BITS: u32 = 9;
+const SUB_BUCKET_COUNT: u64 = 1 << SUB_BUCKET_BITS;
+const SUB_BUCKET_HALF: u64 = SUB_BUCKET_COUNT / 2;
+
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistogram {
+    counts: Vec<u64>,
+    total_count: u64,
+    min: u64,
+    max: u64,
+    mean: f64,
+    squared_deviations: f64,
+}
+
+fn bucket_index(value: u64) -> usize {
+    if value < SUB_BUCKET_COUNT {
+        return value as usize;
+    }
+    let magnitude = 63 - value.leading_zeros();
+    let shift = magnitude - (SUB_BUCKET_BITS - 1);
+    let sub_bucket = value >> shift;
+    (shift as u64 * SUB_BUCKET_HALF + sub_bucket) as usize
+}
+
+fn highest_equivalent_value(index: usize) -> u64 {
+    let index = index as u64;
+    if index < SUB_BUCKET_COUNT {
+        return index;
+    }
+    let shift = index / SUB_BUCKET_HALF - 1;
+    let sub_bucket = index - shift * SUB_BUCKET_HALF;
+    ((sub_bucket + 1) << shift) - 1
+}
+
+impl LatencyHistogram {
+    pub fn new() -> LatencyHistogram {
+        LatencyHistogram::default()
+    }
+
+    pub fn record(&mut self, value: u64) {
+        let index = bucket_index(value);
+        if index >= self.counts.len() {
+            self.counts.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+        if self.total_count == 0 || value < self.min {
+            self.min = value;
+        }
+        if value > self.max {
+            self.max = value;
+        }
+        self.total_count += 1;
+        let delta = value as f64 - self.mean;
+        self.mean += delta / self.total_count as f64;
+        self.squared_deviations += delta * (value as f64 - self.mean);
+    }
+
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        if other.total_count == 0 {
+            return;
+        }
+        if other.counts.len() > self.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (index, count) in other.counts.iter().enumerate() {
+            self.counts[index] += count;
+        }
+        if self.total_count == 0 || other.min < self.min {
+            self.min = other.min;
+        }
+        self.max = self.max.max(other.max);
+        let total_count = (self.total_count + other.total_count) as f64;
+        let delta = other.mean - self.mean;
+        self.squared_deviations += other.squared_deviations
+            + delta.powi(2) * self.total_count as f64 * other.total_count as f64 / total_count;
+        self.mean += delta * other.total_count as f64 / total_count;
+        self.total_count += other.total_count;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.total_count
+    }
+
+    pub fn min(&self) -> u64 {
+        self.min
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    pub fn stdev(&self) -> f64 {
+        if self.total_count == 0 {
+            return 0.0;
+        }
+        (self.squared_deviations / self.total_count as f64).sqrt()
+    }
+
+    pub fn value_at_percentile(&self, percentile: f64) -> u64 {
+        if self.total_count == 0 {
+            return 0;
+        }
+        let rank = ((percentile / 100.0) * self.total_count as f64).ceil() as u64;
+        let rank = rank.clamp(1, self.total_count);
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return highest_equivalent_value(index).min(self.max);
+            }
+        }
+        self.max
+    }
+}
+
+#[cfg(test)]
+mod test_histogram {
+    use super::{bucket_index, highest_equivalent_value, LatencyHistogram};
+
+    #[test]
+    fn test_small_values_are_exact() {
+        let mut histogram = LatencyHistogram::new();
+        for value in 1..=100 {
+            histogram.record(value);
+        }
+        assert_eq!(histogram.value_at_percentile(50.0), 50);
+        assert_eq!(histogram.value_at_percentile(99.0), 99);
+        assert_eq!(histogram.value_at_percentile(100.0), 100);
+        assert_eq!(histogram.min(), 1);
+    }
+
+    #[test]
+    fn test_large_values_within_precision() {
+        let mut histogram = LatencyHistogram::new();
+        histogram.record(1_234_567);
+        let reported = histogram.value_at_percentile(50.0) as f64;
+        assert!((reported - 1_234_567.0).abs() / 1_234_567.0 < 0.004);
+    }
+
+    #[test]
+    fn test_bucket_bounds_are_continuous() {
+        for value in [511, 512, 1023, 1024, 4097, 1_000_000, u32::MAX as u64] {
+            let index = bucket_index(value);
+            assert!(highest_equivalent_value(index) >= value);
+            if index > 0 {
+                assert!(highest_equivalent_value(index - 1) < value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge_matches_single_histogram() {
+        let mut merged = LatencyHistogram::new();
+        let mut single = LatencyHistogram::new();
+        for worker in 0..4u64 {
+            let mut histogram = LatencyHistogram::new();
+            for value in 0..1000u64 {
+                histogram.record(value * 37 + worker * 1000);
+                single.record(value * 37 + worker * 1000);
+            }
+            merged.merge(&histogram);
+        }
+        assert_eq!(merged.count(), single.count());
+        assert_eq!(merged.max(), single.max());
+        for percentile in [50.0, 75.0, 90.0, 99.0, 99.9] {
+            assert_eq!(
+                merged.value_at_percentile(percentile),
+                single.value_at_percentile(percentile)
+            );
+        }
+    }
+}
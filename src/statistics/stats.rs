@@ -1,78 +1,426 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use crate::statistics::histogram::LatencyHistogram;
 use crate::utils;
 use colored::Colorize;
 
+pub const REPORTED_PERCENTILES: [f64; 5] = [50.0, 75.0, 90.0, 99.0, 99.9];
+const MAX_PRINTED_INTERVALS: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RequestError {
+    ConnectRefused,
+    ConnectTimeout,
+    TlsError,
+    ConnectionReset,
+    BrokenPipe,
+    ReadTimeout,
+    ParseError,
+    UnexpectedEof,
+    Other,
+}
+
+impl RequestError {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RequestError::ConnectRefused => "connect_refused",
+            RequestError::ConnectTimeout => "connect_timeout",
+            RequestError::TlsError => "tls_error",
+            RequestError::ConnectionReset => "connection_reset",
+            RequestError::BrokenPipe => "broken_pipe",
+            RequestError::ReadTimeout => "read_timeout",
+            RequestError::ParseError => "parse_error",
+            RequestError::UnexpectedEof => "unexpected_eof",
+            RequestError::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EndpointStats {
+    pub name: String,
+    pub request_count: u32,
+    pub error_count: u32,
+    pub bad_requests: u32,
+    pub latencies: LatencyHistogram,
+}
+
+impl EndpointStats {
+    pub fn new(name: &str) -> EndpointStats {
+        EndpointStats {
+            name: name.to_string(),
+            request_count: 0,
+            error_count: 0,
+            bad_requests: 0,
+            latencies: LatencyHistogram::new(),
+        }
+    }
+
+    pub fn merge(&mut self, other: &EndpointStats) {
+        self.request_count += other.request_count;
+        self.error_count += other.error_count;
+        self.bad_requests += other.bad_requests;
+        self.latencies.merge(&other.latencies);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IntervalStats {
+    pub request_count: u32,
+    pub error_count: u32,
+    pub bad_requests: u32,
+    pub received_data: usize,
+    pub latencies: LatencyHistogram,
+}
+
+impl IntervalStats {
+    pub fn merge(&mut self, other: &IntervalStats) {
+        self.request_count += other.request_count;
+        self.error_count += other.error_count;
+        self.bad_requests += other.bad_requests;
+        self.received_data += other.received_data;
+        self.latencies.merge(&other.latencies);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StageStats {
+    pub name: String,
+    pub duration: Duration,
+    pub request_count: u32,
+    pub error_count: u32,
+    pub bad_requests: u32,
+    pub latencies: LatencyHistogram,
+}
+
+impl StageStats {
+    pub fn new(name: &str, duration: Duration) -> StageStats {
+        StageStats {
+            name: name.to_string(),
+            duration,
+            request_count: 0,
+            error_count: 0,
+            bad_requests: 0,
+            latencies: LatencyHistogram::new(),
+        }
+    }
+
+    pub fn merge(&mut self, other: &StageStats) {
+        self.request_count += other.request_count;
+        self.error_count += other.error_count;
+        self.bad_requests += other.bad_requests;
+        self.latencies.merge(&other.latencies);
+    }
+}
+
+pub fn interval_at(intervals: &mut Vec<IntervalStats>, index: usize) -> &mut IntervalStats {
+    if index >= intervals.len() {
+        intervals.resize_with(index + 1, IntervalStats::default);
+    }
+    &mut intervals[index]
+}
+
+#[derive(Debug, Clone)]
+pub struct ProgressSnapshot {
+    pub worker: usize,
+    pub warmup: bool,
+    pub interval: usize,
+    pub stats: IntervalStats,
+    pub in_flight: usize,
+}
+
+#[derive(Debug)]
+pub enum WorkerMessage {
+    Progress(ProgressSnapshot),
+    WarmupFinished(Box<WorkerStats>),
+    // sent by every worker after its last snapshot, with its index
+    Finished(usize, Box<WorkerStats>),
+}
+
 #[derive(Debug)]
 pub struct WorkerStats {
-    run_duration: usize,
-    request_count: u32,
-    error_count: u32,
-    bad_requests: u32,
-    received_data: usize,
-    mean_latency: f64,
-    stdev_latency: f64,
+    pub run_duration: Duration,
+    pub request_count: u32,
+    pub error_count: u32,
+    pub errors_by_kind: BTreeMap<RequestError, u32>,
+    pub bad_requests: u32,
+    pub received_data: usize,
+    pub latencies: LatencyHistogram,
+    pub corrected_latencies: LatencyHistogram,
+    pub handshake_latencies: LatencyHistogram,
+    pub connect_latencies: LatencyHistogram,
+    pub first_byte_latencies: LatencyHistogram,
+    pub download_latencies: LatencyHistogram,
+    pub status_codes: BTreeMap<u16, u32>,
+    pub endpoints: Vec<EndpointStats>,
+    pub stages: Vec<StageStats>,
+    pub intervals: Vec<IntervalStats>,
+    pub interval_duration: Duration,
 }
 
 impl WorkerStats {
     pub fn new(
-        run_duration: usize,
+        run_duration: Duration,
         request_count: u32,
         error_count: u32,
         bad_requests: u32,
         received_data: usize,
     ) -> Self {
-        return WorkerStats {
+        WorkerStats {
             run_duration,
             request_count,
             error_count,
+            errors_by_kind: BTreeMap::new(),
             bad_requests,
             received_data,
-            mean_latency: 0.0,
-            stdev_latency: 0.0,
-        };
+            latencies: LatencyHistogram::new(),
+            corrected_latencies: LatencyHistogram::new(),
+            handshake_latencies: LatencyHistogram::new(),
+            connect_latencies: LatencyHistogram::new(),
+            first_byte_latencies: LatencyHistogram::new(),
+            download_latencies: LatencyHistogram::new(),
+            status_codes: BTreeMap::new(),
+            endpoints: vec![],
+            stages: vec![],
+            intervals: vec![],
+            interval_duration: Duration::from_secs(1),
+        }
     }
 
-    pub fn calculate_latencies(&mut self, latencies: Vec<f64>) {
-        self.mean_latency = latencies.iter().sum::<f64>() / self.request_count as f64;
-        let latency_variation: f64 = latencies
-            .iter()
-            .map(|lat| (lat - self.mean_latency).powi(2))
-            .sum();
-        self.stdev_latency = (latency_variation / self.request_count as f64).sqrt();
+    pub fn set_latencies(&mut self, latencies: LatencyHistogram) {
+        self.latencies = latencies;
+    }
+
+    pub fn set_status_codes(&mut self, status_codes: BTreeMap<u16, u32>) {
+        self.status_codes = status_codes;
+    }
+
+    pub fn set_endpoints(&mut self, endpoints: Vec<EndpointStats>) {
+        self.endpoints = endpoints;
+    }
+
+    pub fn set_stages(&mut self, stages: Vec<StageStats>) {
+        self.stages = stages;
+    }
+
+    pub fn set_intervals(&mut self, intervals: Vec<IntervalStats>, interval_duration: Duration) {
+        self.intervals = intervals;
+        self.interval_duration = interval_duration;
+    }
+
+    pub fn record_error(
+        &mut self,
+        kind: RequestError,
+        interval: usize,
+        stage: Option<usize>,
+        endpoint: Option<usize>,
+    ) {
+        self.error_count += 1;
+        *self.errors_by_kind.entry(kind).or_insert(0) += 1;
+        interval_at(&mut self.intervals, interval).error_count += 1;
+        if let Some(stage) = stage.and_then(|index| self.stages.get_mut(index)) {
+            stage.error_count += 1;
+        }
+        if let Some(endpoint) = endpoint.and_then(|index| self.endpoints.get_mut(index)) {
+            endpoint.error_count += 1;
+        }
+    }
+}
+
+pub fn per_second(count: u32, duration: Duration) -> f64 {
+    match duration.is_zero() {
+        true => 0.0,
+        false => count as f64 / duration.as_secs_f64(),
+    }
+}
+
+fn print_latency_distribution(title: &str, latencies: &LatencyHistogram) {
+    println!();
+    println!("{}", title.cyan().bold().underline());
+    for percentile in REPORTED_PERCENTILES {
+        println!(
+            "\t{:>6}%\t\t\t {:.2}ms",
+            percentile,
+            latencies.value_at_percentile(percentile) as f64 / 1000.0
+        );
+    }
+    println!(
+        "\t{:>7}\t\t\t {:.2}ms",
+        "max",
+        latencies.max() as f64 / 1000.0
+    );
+}
+
+fn print_phase(phase: &str, latencies: &LatencyHistogram) {
+    if latencies.count() == 0 {
+        return;
     }
+    println!(
+        "\t{:<16}\t {:.2}ms\t\t {:.2}ms\t\t {:.2}ms\t\t {:.2}ms",
+        phase,
+        latencies.mean() / 1000.0,
+        latencies.value_at_percentile(50.0) as f64 / 1000.0,
+        latencies.value_at_percentile(99.0) as f64 / 1000.0,
+        latencies.max() as f64 / 1000.0
+    );
 }
 
 pub struct SummaryStatistics {
-    workers_stats: Vec<WorkerStats>,
-    rps: u32,
-    non_200_300_requests: u32,
-    total_errors: u32,
-    mean_latencies: f64,
-    total_data_received: usize,
+    pub workers_stats: Vec<WorkerStats>,
+    pub total_requests: u32,
+    pub rps: f64,
+    pub run_duration: Duration,
+    pub non_200_300_requests: u32,
+    pub total_errors: u32,
+    pub errors_by_kind: BTreeMap<RequestError, u32>,
+    pub latencies: LatencyHistogram,
+    pub corrected_latencies: LatencyHistogram,
+    pub handshake_latencies: LatencyHistogram,
+    pub dns_lookup_time: Option<Duration>,
+    pub connect_latencies: LatencyHistogram,
+    pub first_byte_latencies: LatencyHistogram,
+    pub download_latencies: LatencyHistogram,
+    pub status_codes: BTreeMap<u16, u32>,
+    pub endpoints: Vec<EndpointStats>,
+    pub stages: Vec<StageStats>,
+    pub intervals: Vec<IntervalStats>,
+    pub interval_duration: Duration,
+    pub total_data_received: usize,
+    pub warmup: Option<Box<SummaryStatistics>>,
 }
 
 impl SummaryStatistics {
     pub fn new(workers_stats: Vec<WorkerStats>) -> SummaryStatistics {
         let mut total_requests = 0;
         let mut total_errors = 0;
+        let mut errors_by_kind: BTreeMap<RequestError, u32> = BTreeMap::new();
         let mut non_200_300_requests = 0;
-        let mut mean_latencies = 0.0;
+        let mut latencies = LatencyHistogram::new();
+        let mut corrected_latencies = LatencyHistogram::new();
+        let mut handshake_latencies = LatencyHistogram::new();
+        let mut connect_latencies = LatencyHistogram::new();
+        let mut first_byte_latencies = LatencyHistogram::new();
+        let mut download_latencies = LatencyHistogram::new();
+        let mut status_codes: BTreeMap<u16, u32> = BTreeMap::new();
+        let mut endpoints: Vec<EndpointStats> = vec![];
+        let mut stages: Vec<StageStats> = vec![];
+        let mut intervals: Vec<IntervalStats> = vec![];
         let mut total_data_received = 0;
-        let job_duration = workers_stats[0].run_duration;
+        let run_duration = workers_stats
+            .iter()
+            .map(|worker| worker.run_duration)
+            .max()
+            .unwrap_or_default();
+        let interval_duration = workers_stats[0].interval_duration;
         for worker in &workers_stats {
             total_requests += worker.request_count;
             total_errors += worker.error_count;
+            for (kind, count) in &worker.errors_by_kind {
+                *errors_by_kind.entry(*kind).or_insert(0) += count;
+            }
             non_200_300_requests += worker.bad_requests;
-            mean_latencies += worker.mean_latency;
+            latencies.merge(&worker.latencies);
+            corrected_latencies.merge(&worker.corrected_latencies);
+            handshake_latencies.merge(&worker.handshake_latencies);
+            connect_latencies.merge(&worker.connect_latencies);
+            first_byte_latencies.merge(&worker.first_byte_latencies);
+            download_latencies.merge(&worker.download_latencies);
+            for (status_code, count) in &worker.status_codes {
+                *status_codes.entry(*status_code).or_insert(0) += count;
+            }
+            for (index, endpoint) in worker.endpoints.iter().enumerate() {
+                match endpoints.get_mut(index) {
+                    Some(merged) => merged.merge(endpoint),
+                    None => endpoints.push(endpoint.clone()),
+                }
+            }
+            for (index, stage) in worker.stages.iter().enumerate() {
+                match stages.get_mut(index) {
+                    Some(merged) => merged.merge(stage),
+                    None => stages.push(stage.clone()),
+                }
+            }
+            for (index, interval) in worker.intervals.iter().enumerate() {
+                interval_at(&mut intervals, index).merge(interval);
+            }
             total_data_received += worker.received_data;
         }
-        return SummaryStatistics {
+        SummaryStatistics {
             non_200_300_requests,
             total_data_received,
             total_errors,
+            errors_by_kind,
             workers_stats,
-            mean_latencies,
-            rps: total_requests / job_duration as u32,
-        };
+            latencies,
+            corrected_latencies,
+            handshake_latencies,
+            dns_lookup_time: None,
+            connect_latencies,
+            first_byte_latencies,
+            download_latencies,
+            status_codes,
+            endpoints,
+            stages,
+            intervals,
+            interval_duration,
+            total_requests,
+            rps: per_second(total_requests, run_duration),
+            run_duration,
+            warmup: None,
+        }
+    }
+
+    pub fn set_warmup(&mut self, warmup: SummaryStatistics) {
+        self.warmup = Some(Box::new(warmup));
+    }
+
+    pub fn set_dns_lookup_time(&mut self, dns_lookup_time: Duration) {
+        self.dns_lookup_time = Some(dns_lookup_time);
+    }
+
+    fn print_timing_breakdown(&self) {
+        println!();
+        println!("{}", "Timing breakdown:".cyan().bold());
+        println!(
+            "{}",
+            "\tphase\t\t\t mean\t\t p50\t\t p99\t\t max"
+                .cyan()
+                .underline()
+        );
+        // the host is resolved once, so there is a single sample
+        if let Some(dns_lookup_time) = self.dns_lookup_time {
+            println!(
+                "\t{:<16}\t {:.2}ms",
+                "dns lookup",
+                dns_lookup_time.as_secs_f64() * 1000.0
+            );
+        }
+        print_phase("tcp connect", &self.connect_latencies);
+        print_phase("tls handshake", &self.handshake_latencies);
+        print_phase("first byte", &self.first_byte_latencies);
+        print_phase("download", &self.download_latencies);
+    }
+
+    fn print_time_series(&self) {
+        let interval_secs = self.interval_duration.as_secs_f64();
+        println!();
+        println!("{}", "Time series:".cyan().bold());
+        println!(
+            "{}",
+            "\tinterval start\t requests/sec\t errors\t\t mean latency\t p99 latency"
+                .cyan()
+                .underline()
+        );
+        for (index, interval) in self.intervals.iter().enumerate() {
+            println!(
+                "\t{:.1}s\t\t {:.2}\t {}\t\t {:.2}ms\t\t {:.2}ms",
+                index as f64 * interval_secs,
+                interval.request_count as f64 / interval_secs,
+                interval.error_count,
+                interval.latencies.mean() / 1000.0,
+                interval.latencies.value_at_percentile(99.0) as f64 / 1000.0
+            );
+        }
     }
 
     pub fn represent(&self) {
@@ -88,57 +436,303 @@ impl SummaryStatistics {
             println!(
                 "\tworker {}\t {:.2}ms\t\t\t {:.2}ms\t\t\t {}\t\t\t {}\t\t {}",
                 index,
-                worker_stat.mean_latency / 1000.0,
-                worker_stat.stdev_latency / 1000.0,
+                worker_stat.latencies.mean() / 1000.0,
+                worker_stat.latencies.stdev() / 1000.0,
                 worker_stat.request_count,
                 worker_stat.error_count,
                 utils::format_received_data_value(worker_stat.received_data)
             );
         }
-        let total_mean_latency = self.mean_latencies / self.workers_stats.len() as f64;
+        if self.corrected_latencies.count() > 0 {
+            print_latency_distribution(
+                "Latency distribution (corrected for coordinated omission):",
+                &self.corrected_latencies,
+            );
+            print_latency_distribution("Uncorrected latency distribution:", &self.latencies);
+        } else {
+            print_latency_distribution("Latency distribution:", &self.latencies);
+        }
+        if self.connect_latencies.count() > 0 || self.first_byte_latencies.count() > 0 {
+            self.print_timing_breakdown();
+        }
+        if !self.endpoints.is_empty() {
+            println!();
+            println!("{}", "Statistics by endpoints:".cyan().bold());
+            println!(
+                "{}",
+                "\trequests/sec\t mean latency\t p99 latency\t not 2**/3**\t errors\t\t endpoint"
+                    .cyan()
+                    .underline()
+            );
+            for endpoint in &self.endpoints {
+                println!(
+                    "\t{:.2}\t\t {:.2}ms\t\t {:.2}ms\t\t {}\t\t {}\t\t {}",
+                    per_second(endpoint.request_count, self.run_duration),
+                    endpoint.latencies.mean() / 1000.0,
+                    endpoint.latencies.value_at_percentile(99.0) as f64 / 1000.0,
+                    endpoint.bad_requests,
+                    endpoint.error_count,
+                    endpoint.name
+                );
+            }
+        }
+        if !self.stages.is_empty() {
+            println!();
+            println!("{}", "Statistics by stages:".cyan().bold());
+            println!(
+                "{}",
+                "\trequests/sec\t mean latency\t p99 latency\t not 2**/3**\t errors\t\t stage"
+                    .cyan()
+                    .underline()
+            );
+            for stage in &self.stages {
+                println!(
+                    "\t{:.2}\t\t {:.2}ms\t\t {:.2}ms\t\t {}\t\t {}\t\t {}",
+                    per_second(stage.request_count, stage.duration),
+                    stage.latencies.mean() / 1000.0,
+                    stage.latencies.value_at_percentile(99.0) as f64 / 1000.0,
+                    stage.bad_requests,
+                    stage.error_count,
+                    stage.name
+                );
+            }
+        }
+        if !self.status_codes.is_empty() {
+            let responses_count: u32 = self.status_codes.values().sum();
+            println!();
+            println!("{}", "Status code breakdown:".cyan().bold().underline());
+            for (status_code, count) in &self.status_codes {
+                let line = format!(
+                    "\t{}\t\t\t\t {:.2}%\t\t ({})",
+                    status_code,
+                    *count as f64 * 100.0 / responses_count as f64,
+                    count
+                );
+                match status_code {
+                    200..=399 => println!("{}", line.green()),
+                    _ => println!("{}", line.red()),
+                }
+            }
+        }
+        if !self.errors_by_kind.is_empty() {
+            println!();
+            println!("{}", "Error breakdown:".cyan().bold().underline());
+            for (kind, count) in &self.errors_by_kind {
+                let line = format!(
+                    "\t{:<16}\t\t {:.2}%\t\t ({})",
+                    kind.name(),
+                    *count as f64 * 100.0 / self.total_errors as f64,
+                    count
+                );
+                println!("{}", line.red());
+            }
+        }
+        if self.intervals.len() > MAX_PRINTED_INTERVALS {
+            println!();
+            println!(
+                "{}",
+                format!(
+                    "Time series has {} intervals, export it with --timeseries-file or --output-file",
+                    self.intervals.len()
+                )
+                .cyan()
+            );
+        } else if !self.intervals.is_empty() {
+            self.print_time_series();
+        }
         println!();
         println!("{}", "Summary:".cyan().bold().underline());
         println!(
             "{}{}",
             "\tRequests per second:\t\t ".bright_green(),
-            format!("{}", self.rps).bright_green()
+            format!("{:.2}", self.rps).bright_green()
+        );
+        println!(
+            "\tRun duration:\t\t\t {:.2}s",
+            self.run_duration.as_secs_f64()
         );
         println!(
             "\tTotal data received:\t\t {}",
             utils::format_received_data_value(self.total_data_received)
         );
-        println!("\tMean latency:\t\t\t {:.2}ms", total_mean_latency / 1000.0);
         println!(
-            "{}{}",
-            "\tNot 2** or 3** server responses: ",
-            format!("{}", self.non_200_300_requests)
+            "\tMean latency:\t\t\t {:.2}ms",
+            self.latencies.mean() / 1000.0
         );
         println!(
-            "{}{}",
-            "\tConnection errors happened:\t ",
-            format!("{}", self.total_errors)
+            "\tStdev latency:\t\t\t {:.2}ms",
+            self.latencies.stdev() / 1000.0
         );
+        println!(
+            "\tNot 2** or 3** server responses: {}",
+            self.non_200_300_requests
+        );
+        println!("\tErrors happened:\t\t {}", self.total_errors);
     }
 }
 
 #[cfg(test)]
 mod test_statistics {
-    use super::WorkerStats;
+    use std::{collections::BTreeMap, time::Duration};
+
+    use super::{
+        EndpointStats, IntervalStats, RequestError, StageStats, SummaryStatistics, WorkerStats,
+    };
+    use crate::statistics::histogram::LatencyHistogram;
+
+    fn histogram_of(values: &[u64]) -> LatencyHistogram {
+        let mut histogram = LatencyHistogram::new();
+        for value in values {
+            histogram.record(*value);
+        }
+        histogram
+    }
 
     #[test]
     fn test_mean_calculation() {
-        let mut worker_stats = WorkerStats::new(1, 3, 0, 0, 0);
-        let latencies = vec![1.0, 2.0, 3.0];
-        worker_stats.calculate_latencies(latencies);
-        assert_eq!(worker_stats.mean_latency, 2.0);
+        let mut worker_stats = WorkerStats::new(Duration::from_secs(1), 3, 0, 0, 0);
+        worker_stats.set_latencies(histogram_of(&[1, 2, 3]));
+        assert_eq!(worker_stats.latencies.mean(), 2.0);
     }
 
     #[test]
     fn test_stdev_calculation() {
-        let mut worker_stats = WorkerStats::new(1, 3, 0, 0, 0);
-        let latencies = vec![1.0, 2.0, 3.0];
-        worker_stats.calculate_latencies(latencies);
+        let mut worker_stats = WorkerStats::new(Duration::from_secs(1), 3, 0, 0, 0);
+        worker_stats.set_latencies(histogram_of(&[1, 2, 3]));
         let dispersion: f64 = 2.0 / 3.0;
-        assert_eq!(worker_stats.stdev_latency, dispersion.sqrt());
+        assert_eq!(worker_stats.latencies.stdev(), dispersion.sqrt());
+    }
+
+    #[test]
+    fn test_latencies_merged_across_workers() {
+        let mut first = WorkerStats::new(Duration::from_secs(1), 2, 0, 0, 0);
+        first.set_latencies(histogram_of(&[10, 20]));
+        let mut second = WorkerStats::new(Duration::from_secs(1), 2, 0, 0, 0);
+        second.set_latencies(histogram_of(&[30, 400]));
+        let summary = SummaryStatistics::new(vec![first, second]);
+        assert_eq!(summary.latencies.count(), 4);
+        assert_eq!(summary.latencies.value_at_percentile(50.0), 20);
+        assert_eq!(summary.latencies.max(), 400);
+        assert_eq!(summary.rps, 4.0);
+    }
+
+    #[test]
+    fn test_phase_latencies_merged_across_workers() {
+        let mut first = WorkerStats::new(Duration::from_secs(1), 1, 0, 0, 0);
+        first.connect_latencies = histogram_of(&[100]);
+        first.first_byte_latencies = histogram_of(&[300]);
+        let mut second = WorkerStats::new(Duration::from_secs(1), 1, 0, 0, 0);
+        second.connect_latencies = histogram_of(&[200]);
+        second.download_latencies = histogram_of(&[50]);
+        let summary = SummaryStatistics::new(vec![first, second]);
+        assert_eq!(summary.connect_latencies.count(), 2);
+        assert_eq!(summary.connect_latencies.max(), 200);
+        assert_eq!(summary.first_byte_latencies.count(), 1);
+        assert_eq!(summary.download_latencies.mean(), 50.0);
+        assert_eq!(summary.dns_lookup_time, None);
+    }
+
+    #[test]
+    fn test_rps_from_measured_duration() {
+        let first = WorkerStats::new(Duration::from_millis(2_000), 300, 0, 0, 0);
+        let second = WorkerStats::new(Duration::from_millis(2_500), 200, 0, 0, 0);
+        let summary = SummaryStatistics::new(vec![first, second]);
+        assert_eq!(summary.run_duration, Duration::from_millis(2_500));
+        assert_eq!(summary.rps, 200.0);
+    }
+
+    #[test]
+    fn test_status_codes_merged_across_workers() {
+        let mut first = WorkerStats::new(Duration::from_secs(1), 3, 0, 1, 0);
+        first.set_status_codes(BTreeMap::from([(200, 2), (503, 1)]));
+        let mut second = WorkerStats::new(Duration::from_secs(1), 2, 0, 1, 0);
+        second.set_status_codes(BTreeMap::from([(200, 1), (429, 1)]));
+        let summary = SummaryStatistics::new(vec![first, second]);
+        assert_eq!(
+            summary.status_codes,
+            BTreeMap::from([(200, 3), (429, 1), (503, 1)])
+        );
+        assert_eq!(summary.non_200_300_requests, 2);
+    }
+
+    #[test]
+    fn test_endpoints_merged_across_workers() {
+        let mut items = EndpointStats::new("GET /items");
+        items.request_count = 2;
+        items.latencies = histogram_of(&[10, 20]);
+        let mut orders = EndpointStats::new("POST /orders");
+        orders.error_count = 1;
+        let mut first = WorkerStats::new(Duration::from_secs(1), 2, 1, 0, 0);
+        first.set_endpoints(vec![items.clone(), orders.clone()]);
+        let mut second = WorkerStats::new(Duration::from_secs(1), 2, 1, 0, 0);
+        second.set_endpoints(vec![items, orders]);
+        let summary = SummaryStatistics::new(vec![first, second]);
+        assert_eq!(summary.endpoints.len(), 2);
+        assert_eq!(summary.endpoints[0].request_count, 4);
+        assert_eq!(summary.endpoints[0].latencies.count(), 4);
+        assert_eq!(summary.endpoints[1].name, "POST /orders");
+        assert_eq!(summary.endpoints[1].error_count, 2);
+    }
+
+    #[test]
+    fn test_stages_merged_across_workers() {
+        let mut ramp = StageStats::new("30s@100rps", Duration::from_secs(30));
+        ramp.request_count = 1500;
+        ramp.latencies = histogram_of(&[10, 20]);
+        let hold = StageStats::new("1m@100rps", Duration::from_secs(60));
+        let mut first = WorkerStats::new(Duration::from_secs(90), 1500, 0, 0, 0);
+        first.set_stages(vec![ramp.clone(), hold.clone()]);
+        let mut second = WorkerStats::new(Duration::from_secs(90), 1500, 0, 0, 0);
+        second.set_stages(vec![ramp, hold]);
+        let summary = SummaryStatistics::new(vec![first, second]);
+        assert_eq!(summary.stages.len(), 2);
+        assert_eq!(summary.stages[0].request_count, 3000);
+        assert_eq!(summary.stages[0].latencies.count(), 4);
+        assert_eq!(summary.stages[1].duration, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_errors_counted_by_kind() {
+        let mut first = WorkerStats::new(Duration::from_secs(1), 0, 0, 0, 0);
+        first.set_endpoints(vec![EndpointStats::new("GET /")]);
+        first.record_error(RequestError::ReadTimeout, 2, None, Some(0));
+        first.record_error(RequestError::ConnectionReset, 0, None, None);
+        assert_eq!(first.intervals.len(), 3);
+        assert_eq!(first.intervals[2].error_count, 1);
+        assert_eq!(first.endpoints[0].error_count, 1);
+        let mut second = WorkerStats::new(Duration::from_secs(1), 0, 0, 0, 0);
+        second.record_error(RequestError::ConnectionReset, 0, None, None);
+        let summary = SummaryStatistics::new(vec![first, second]);
+        assert_eq!(summary.total_errors, 3);
+        assert_eq!(
+            summary.errors_by_kind,
+            BTreeMap::from([
+                (RequestError::ConnectionReset, 2),
+                (RequestError::ReadTimeout, 1)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_intervals_merged_across_workers() {
+        let interval = IntervalStats {
+            request_count: 3,
+            received_data: 100,
+            latencies: histogram_of(&[10, 20, 30]),
+            ..Default::default()
+        };
+        let mut first = WorkerStats::new(Duration::from_secs(2), 6, 0, 0, 200);
+        first.set_intervals(
+            vec![interval.clone(), interval.clone()],
+            Duration::from_millis(500),
+        );
+        let mut second = WorkerStats::new(Duration::from_secs(2), 3, 0, 0, 100);
+        second.set_intervals(vec![interval], Duration::from_millis(500));
+        let summary = SummaryStatistics::new(vec![first, second]);
+        assert_eq!(summary.intervals.len(), 2);
+        assert_eq!(summary.intervals[0].request_count, 6);
+        assert_eq!(summary.intervals[0].latencies.count(), 6);
+        assert_eq!(summary.intervals[1].received_data, 100);
+        assert_eq!(summary.interval_duration, Duration::from_millis(500));
     }
 }
@@ -1,4 +1,3 @@
-use colored::Colorize;
 use std::{
     sync::{
         mpsc::{channel, Receiver, Sender},
@@ -9,7 +8,7 @@ use std::{
 
 use crate::{
     jobs::job::Job,
-    statistics::stats::{SummaryStatistics, WorkerStats},
+    statistics::stats::{SummaryStatistics, WorkerMessage, WorkerStats},
 };
 
 pub struct Worker {
@@ -18,14 +17,15 @@ pub struct Worker {
 
 impl Worker {
     pub fn new(
+        worker_index: usize,
         receiver: Arc<Mutex<Receiver<Box<dyn Job + Sync + Send>>>>,
-        stats_sender: Sender<WorkerStats>,
+        stats_sender: Sender<WorkerMessage>,
     ) -> Worker {
         let thread: JoinHandle<()> = spawn(move || {
             let mut local_job: Box<dyn Job + Send + Sync> =
                 receiver.lock().unwrap().recv().unwrap();
             drop(receiver);
-            local_job.execute(stats_sender);
+            local_job.execute(worker_index, stats_sender);
         });
         Worker {
             thread: Some(thread),
@@ -35,7 +35,7 @@ impl Worker {
 pub struct ThreadPool {
     workers_pool: Vec<Worker>,
     sender: Option<Sender<Box<dyn Job + Send + Sync>>>,
-    stats_recvr: Receiver<WorkerStats>,
+    stats_recvr: Receiver<WorkerMessage>,
 }
 
 impl ThreadPool {
@@ -43,13 +43,13 @@ impl ThreadPool {
         let mut workers = Vec::with_capacity(num_threads as usize);
         let (sender, receiver) = channel::<Box<dyn Job + Send + Sync>>();
         let receiver = Arc::new(Mutex::new(receiver));
-        let (stats_tx, stats_rx) = channel::<WorkerStats>();
-        println!(
-            "{}",
-            format!("Spawning workers: {}", num_threads).cyan().bold()
-        );
-        for _ in 0..num_threads {
-            workers.push(Worker::new(Arc::clone(&receiver), stats_tx.clone()));
+        let (stats_tx, stats_rx) = channel::<WorkerMessage>();
+        for worker_index in 0..num_threads as usize {
+            workers.push(Worker::new(
+                worker_index,
+                Arc::clone(&receiver),
+                stats_tx.clone(),
+            ));
         }
         ThreadPool {
             workers_pool: workers,
@@ -58,17 +58,31 @@ impl ThreadPool {
         }
     }
 
-    pub fn start(&self, j: Box<dyn Job + Send + Sync>) {
+    pub fn start(
+        &self,
+        j: Box<dyn Job + Send + Sync>,
+        on_message: &mut dyn FnMut(&WorkerMessage),
+    ) -> SummaryStatistics {
         for _ in 0..self.workers_pool.len() {
             let new_job = j.clone_job();
             self.sender.as_ref().unwrap().send(new_job).unwrap();
         }
+        let mut warmup_stats: Vec<WorkerStats> = vec![];
         let mut workers_stats: Vec<WorkerStats> = vec![];
-        for _ in 0..self.workers_pool.len() {
-            let recvd_stats = self.stats_recvr.recv().unwrap();
-            workers_stats.push(recvd_stats);
+        while workers_stats.len() < self.workers_pool.len() {
+            let message = self.stats_recvr.recv().unwrap();
+            on_message(&message);
+            match message {
+                WorkerMessage::Progress(_) => {}
+                WorkerMessage::WarmupFinished(recvd_stats) => warmup_stats.push(*recvd_stats),
+                WorkerMessage::Finished(_, recvd_stats) => workers_stats.push(*recvd_stats),
+            }
+        }
+        let mut summary = SummaryStatistics::new(workers_stats);
+        if !warmup_stats.is_empty() {
+            summary.set_warmup(SummaryStatistics::new(warmup_stats));
         }
-        SummaryStatistics::new(workers_stats).represent();
+        summary
     }
 }
 
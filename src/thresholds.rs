@@ -0,0 +1,247 @@
+use serde::Serialize;
+
+use crate::statistics::stats::SummaryStatistics;
+use crate::utils::parse_duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Metric {
+    Percentile(f64),
+    MeanLatency,
+    MaxLatency,
+    ErrorRate,
+    Rps,
+    Requests,
+    Errors,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn holds(&self, actual: f64, limit: f64) -> bool {
+        match self {
+            Comparison::Less => actual < limit,
+            Comparison::LessOrEqual => actual <= limit,
+            Comparison::Greater => actual > limit,
+            Comparison::GreaterOrEqual => actual >= limit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Threshold {
+    pub expression: String,
+    metric: Metric,
+    comparison: Comparison,
+    limit: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ThresholdResult {
+    pub expression: String,
+    pub metric: String,
+    pub actual: String,
+    pub passed: bool,
+}
+
+fn parse_metric(name: &str) -> Result<Metric, String> {
+    match name {
+        "mean" => Ok(Metric::MeanLatency),
+        "max" => Ok(Metric::MaxLatency),
+        "error_rate" => Ok(Metric::ErrorRate),
+        "rps" => Ok(Metric::Rps),
+        "requests" => Ok(Metric::Requests),
+        "errors" => Ok(Metric::Errors),
+        name => match name.strip_prefix('p').map(str::parse::<f64>) {
+            Some(Ok(percentile)) if (0.0..=100.0).contains(&percentile) => {
+                Ok(Metric::Percentile(percentile))
+            }
+            _ => Err(format!(
+                "unknown metric \"{name}\", use pNN, mean, max, error_rate, rps, requests or errors"
+            )),
+        },
+    }
+}
+
+fn parse_limit(metric: Metric, value: &str) -> Result<f64, String> {
+    match metric {
+        // a bare number is ambiguous between seconds and milliseconds
+        Metric::Percentile(_) | Metric::MeanLatency | Metric::MaxLatency
+            if value.ends_with(|character: char| character.is_ascii_digit()) =>
+        {
+            Err(format!(
+                "latency limit {value} needs a unit such as 250ms or 1s"
+            ))
+        }
+        Metric::Percentile(_) | Metric::MeanLatency | Metric::MaxLatency => {
+            parse_duration(value).map(|duration| duration.as_micros() as f64)
+        }
+        Metric::ErrorRate => match value.strip_suffix('%') {
+            Some(percent) => percent.trim().parse::<f64>().map(|rate| rate / 100.0),
+            None => value.parse::<f64>(),
+        }
+        .map_err(|_| format!("invalid rate: {value}")),
+        Metric::Rps | Metric::Requests | Metric::Errors => value
+            .parse::<f64>()
+            .map_err(|_| format!("invalid number: {value}")),
+    }
+}
+
+fn format_latency(microseconds: f64) -> String {
+    format!("{:.2}ms", microseconds / 1000.0)
+}
+
+impl Threshold {
+    pub fn parse(expression: &str) -> Result<Threshold, String> {
+        let Some(position) = expression.find(['<', '>']) else {
+            return Err(format!(
+                "threshold \"{expression}\" must be in \"metric<value\" or \"metric>value\" form"
+            ));
+        };
+        let (name, rest) = expression.split_at(position);
+        let (comparison, value) = match rest {
+            rest if rest.starts_with("<=") => (Comparison::LessOrEqual, &rest[2..]),
+            rest if rest.starts_with(">=") => (Comparison::GreaterOrEqual, &rest[2..]),
+            rest if rest.starts_with('<') => (Comparison::Less, &rest[1..]),
+            rest => (Comparison::Greater, &rest[1..]),
+        };
+        let metric = parse_metric(name.trim())?;
+        let limit = parse_limit(metric, value.trim())
+            .map_err(|e| format!("threshold \"{expression}\": {e}"))?;
+        Ok(Threshold {
+            expression: expression.to_string(),
+            metric,
+            comparison,
+            limit,
+        })
+    }
+
+    pub fn evaluate(&self, summary: &SummaryStatistics) -> ThresholdResult {
+        let latencies = match summary.corrected_latencies.count() {
+            0 => &summary.latencies,
+            _ => &summary.corrected_latencies,
+        };
+        let (actual, formatted) = match self.metric {
+            Metric::Percentile(percentile) => {
+                let value = latencies.value_at_percentile(percentile) as f64;
+                (value, format_latency(value))
+            }
+            Metric::MeanLatency => (latencies.mean(), format_latency(latencies.mean())),
+            Metric::MaxLatency => {
+                let value = latencies.max() as f64;
+                (value, format_latency(value))
+            }
+            Metric::ErrorRate => {
+                let failed = (summary.total_errors + summary.non_200_300_requests) as f64;
+                let attempted = (summary.total_requests + summary.total_errors).max(1) as f64;
+                let rate = failed / attempted;
+                (rate, format!("{:.3}%", rate * 100.0))
+            }
+            Metric::Rps => (summary.rps, format!("{:.2}", summary.rps)),
+            Metric::Requests => (
+                summary.total_requests as f64,
+                summary.total_requests.to_string(),
+            ),
+            Metric::Errors => (
+                summary.total_errors as f64,
+                summary.total_errors.to_string(),
+            ),
+        };
+        let metric = self
+            .expression
+            .split(['<', '>'])
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        ThresholdResult {
+            expression: self.expression.clone(),
+            metric,
+            actual: formatted,
+            passed: self.comparison.holds(actual, self.limit),
+        }
+    }
+}
+
+pub fn evaluate_thresholds(
+    thresholds: &[Threshold],
+    summary: &SummaryStatistics,
+) -> Vec<ThresholdResult> {
+    thresholds
+        .iter()
+        .map(|threshold| threshold.evaluate(summary))
+        .collect()
+}
+
+#[cfg(test)]
+mod test_thresholds {
+    use std::{collections::BTreeMap, time::Duration};
+
+    use super::Threshold;
+    use crate::statistics::{
+        histogram::LatencyHistogram,
+        stats::{SummaryStatistics, WorkerStats},
+    };
+
+    fn summary() -> SummaryStatistics {
+        let mut latencies = LatencyHistogram::new();
+        for latency in 1..=100 {
+            latencies.record(latency * 10);
+        }
+        let mut worker_stats = WorkerStats::new(Duration::from_secs(10), 1000, 5, 5, 0);
+        worker_stats.set_latencies(latencies);
+        worker_stats.set_status_codes(BTreeMap::from([(200, 995), (503, 5)]));
+        SummaryStatistics::new(vec![worker_stats])
+    }
+
+    #[test]
+    fn test_parse_thresholds() {
+        assert!(Threshold::parse("p99<250ms").is_ok());
+        assert!(Threshold::parse("p99.9 <= 1s").is_ok());
+        assert!(Threshold::parse("error_rate<0.1%").is_ok());
+        assert!(Threshold::parse("rps>5000").is_ok());
+        assert!(Threshold::parse("p99=250ms").is_err());
+        assert!(Threshold::parse("p101<250ms").is_err());
+        assert!(Threshold::parse("latency<250ms").is_err());
+        assert!(Threshold::parse("rps>fast").is_err());
+        assert!(Threshold::parse("p99<250").is_err());
+        assert!(Threshold::parse("mean<0.5").is_err());
+    }
+
+    #[test]
+    fn test_evaluate_thresholds() {
+        let summary = summary();
+        let result = Threshold::parse("p99<1s").unwrap().evaluate(&summary);
+        assert!(result.passed);
+        assert_eq!(result.metric, "p99");
+        assert_eq!(result.actual, "0.99ms");
+        assert!(
+            !Threshold::parse("p50<0.5ms")
+                .unwrap()
+                .evaluate(&summary)
+                .passed
+        );
+        assert!(
+            Threshold::parse("rps>=100")
+                .unwrap()
+                .evaluate(&summary)
+                .passed
+        );
+        let result = Threshold::parse("error_rate<0.5%")
+            .unwrap()
+            .evaluate(&summary);
+        assert!(!result.passed);
+        assert_eq!(result.actual, "0.995%");
+        assert!(
+            Threshold::parse("error_rate<0.01")
+                .unwrap()
+                .evaluate(&summary)
+                .passed
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use std::sync::Arc;
+
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider},
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, ServerName, UnixTime},
+    ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+
+#[derive(Debug, Default, Clone)]
+pub struct TlsOptions {
+    pub sni: Option<String>,
+    pub ca_file: Option<String>,
+    pub cert_file: Option<String>,
+    pub key_file: Option<String>,
+    pub insecure: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct TlsSettings {
+    pub config: Arc<ClientConfig>,
+    pub server_name: ServerName<'static>,
+}
+
+impl TlsSettings {
+    pub fn new(host: &str, options: &TlsOptions) -> Result<TlsSettings, String> {
+        let name = options.sni.clone().unwrap_or(host.to_string());
+        let server_name = ServerName::try_from(name.clone())
+            .map_err(|_| format!("invalid TLS server name: {name}"))?;
+        Ok(TlsSettings {
+            config: Arc::new(build_client_config(options)?),
+            server_name,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct NoCertificateVerification(CryptoProvider);
+
+impl ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn load_certificates(path: &str) -> Result<Vec<CertificateDer<'static>>, String> {
+    let certificates = CertificateDer::pem_file_iter(path)
+        .map_err(|e| format!("unable to read certificates from {path}: {e}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("unable to parse certificates from {path}: {e}"))?;
+    if certificates.is_empty() {
+        return Err(format!("no certificates found in {path}"));
+    }
+    Ok(certificates)
+}
+
+fn load_root_store(ca_file: Option<&String>) -> Result<RootCertStore, String> {
+    match ca_file {
+        Some(path) => {
+            let mut root_store = RootCertStore::empty();
+            for certificate in load_certificates(path)? {
+                root_store
+                    .add(certificate)
+                    .map_err(|e| format!("invalid CA certificate in {path}: {e}"))?;
+            }
+            Ok(root_store)
+        }
+        None => Ok(RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        }),
+    }
+}
+
+pub fn build_client_config(options: &TlsOptions) -> Result<ClientConfig, String> {
+    let provider = Arc::new(ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("unable to configure TLS: {e}"))?;
+    let builder = match options.insecure {
+        true => builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoCertificateVerification(
+                provider.as_ref().clone(),
+            ))),
+        false => builder.with_root_certificates(load_root_store(options.ca_file.as_ref())?),
+    };
+    let mut config = match (&options.cert_file, &options.key_file) {
+        (Some(cert_file), Some(key_file)) => {
+            let key = PrivateKeyDer::from_pem_file(key_file)
+                .map_err(|e| format!("unable to read private key from {key_file}: {e}"))?;
+            builder
+                .with_client_auth_cert(load_certificates(cert_file)?, key)
+                .map_err(|e| format!("invalid client certificate: {e}"))?
+        }
+        (None, None) => builder.with_no_client_auth(),
+        _ => return Err("client certificate and key must be provided together".to_string()),
+    };
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+#[cfg(test)]
+mod test_tls {
+    use std::sync::Arc;
+
+    use rustls::{
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+        server::WebPkiClientVerifier,
+        ClientConnection, RootCertStore, ServerConfig, ServerConnection,
+    };
+
+    use super::{TlsOptions, TlsSettings};
+
+    const CA_FILE: &str = "tests/fixtures/ca.crt";
+    const CERT_FILE: &str = "tests/fixtures/localhost.crt";
+    const KEY_FILE: &str = "tests/fixtures/localhost.key";
+
+    fn server_config(require_client_cert: bool) -> Arc<ServerConfig> {
+        let certificates = CertificateDer::pem_file_iter(CERT_FILE)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let key = PrivateKeyDer::from_pem_file(KEY_FILE).unwrap();
+        let builder = ServerConfig::builder();
+        let builder = match require_client_cert {
+            true => {
+                let mut roots = RootCertStore::empty();
+                roots
+                    .add(CertificateDer::from_pem_file(CA_FILE).unwrap())
+                    .unwrap();
+                builder.with_client_cert_verifier(
+                    WebPkiClientVerifier::builder(Arc::new(roots))
+                        .build()
+                        .unwrap(),
+                )
+            }
+            false => builder.with_no_client_auth(),
+        };
+        Arc::new(builder.with_single_cert(certificates, key).unwrap())
+    }
+
+    fn handshake(settings: TlsSettings, server_config: Arc<ServerConfig>) -> Result<(), String> {
+        let mut client = ClientConnection::new(settings.config, settings.server_name).unwrap();
+        let mut server = ServerConnection::new(server_config).unwrap();
+        while client.is_handshaking() || server.is_handshaking() {
+            let mut buffer = Vec::new();
+            while client.wants_write() {
+                client.write_tls(&mut buffer).unwrap();
+            }
+            server.read_tls(&mut buffer.as_slice()).unwrap();
+            server.process_new_packets().map_err(|e| e.to_string())?;
+            let mut buffer = Vec::new();
+            while server.wants_write() {
+                server.write_tls(&mut buffer).unwrap();
+            }
+            client.read_tls(&mut buffer.as_slice()).unwrap();
+            client.process_new_packets().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_handshake_with_custom_ca() {
+        let options = TlsOptions {
+            ca_file: Some(CA_FILE.to_string()),
+            ..Default::default()
+        };
+        let settings = TlsSettings::new("localhost", &options).unwrap();
+        assert!(handshake(settings, server_config(false)).is_ok());
+    }
+
+    #[test]
+    fn test_self_signed_rejected_by_default() {
+        let settings = TlsSettings::new("localhost", &TlsOptions::default()).unwrap();
+        assert!(handshake(settings, server_config(false)).is_err());
+    }
+
+    #[test]
+    fn test_insecure_accepts_any_certificate() {
+        let options = TlsOptions {
+            insecure: true,
+            sni: Some("example.com".to_string()),
+            ..Default::default()
+        };
+        let settings = TlsSettings::new("127.0.0.1", &options).unwrap();
+        assert!(handshake(settings, server_config(false)).is_ok());
+    }
+
+    #[test]
+    fn test_client_certificate() {
+        let options = TlsOptions {
+            ca_file: Some(CA_FILE.to_string()),
+            cert_file: Some(CERT_FILE.to_string()),
+            key_file: Some(KEY_FILE.to_string()),
+            ..Default::default()
+        };
+        let settings = TlsSettings::new("localhost", &options).unwrap();
+        assert!(handshake(settings, server_config(true)).is_ok());
+    }
+
+    #[test]
+    fn test_missing_ca_file() {
+        let options = TlsOptions {
+            ca_file: Some("tests/fixtures/missing.crt".to_string()),
+            ..Default::default()
+        };
+        assert!(TlsSettings::new("localhost", &options).is_err());
+    }
+}
@@ -1,98 +1,312 @@
 use regex::Regex;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scheme {
+    Http,
+    Https,
+}
+
+impl Scheme {
+    fn default_port(&self) -> u16 {
+        match self {
+            Scheme::Http => 80,
+            Scheme::Https => 443,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ParsedUrlHeader {
+    pub scheme: Scheme,
     pub host: String,
     pub resource: String,
     pub port: u16,
-    pub header: Option<String>,
+    pub headers: Vec<(String, String)>,
+    pub default_headers: bool,
+    pub keep_alive: bool,
+    pub method: String,
+    pub body: Option<Vec<u8>>,
+}
+
+fn is_token_char(character: char) -> bool {
+    character.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(character)
+}
+
+fn is_field_value_char(character: char) -> bool {
+    character == ' '
+        || character == '\t'
+        || ('!'..='~').contains(&character)
+        || !character.is_ascii()
+}
+
+pub fn parse_header(header: &str) -> Result<(String, String), String> {
+    let Some((name, value)) = header.split_once(':') else {
+        return Err(format!(
+            "header \"{header}\" must be in \"Name: value\" form"
+        ));
+    };
+    if name.is_empty() {
+        return Err(format!("header \"{header}\" has an empty name"));
+    }
+    if let Some(character) = name.chars().find(|character| !is_token_char(*character)) {
+        return Err(format!(
+            "header name \"{name}\" contains invalid character {character:?}"
+        ));
+    }
+    let value = value.trim_matches([' ', '\t']);
+    if let Some(character) = value
+        .chars()
+        .find(|character| !is_field_value_char(*character))
+    {
+        return Err(format!(
+            "value of header \"{name}\" contains invalid character {character:?}"
+        ));
+    }
+    Ok((name.to_string(), value.to_string()))
 }
 
 impl ParsedUrlHeader {
     pub fn parse_url(url: &str) -> Result<ParsedUrlHeader, String> {
-        let re =
-            Regex::new(r"(www\.|)([a-zA-Z0-9]+\.[a-z]+|localhost|\d+\.\d+\.\d+\.\d+)(:\d+|)(.*)")
-                .unwrap();
+        let re = Regex::new(
+            r"^(?:([a-zA-Z][a-zA-Z0-9+.-]*)://)?((?:[a-zA-Z0-9-]+\.)*[a-zA-Z0-9-]+)(:\d+|)(.*)$",
+        )
+        .unwrap();
         let caps = re.captures(url);
         match caps {
             None => Err("Match not found".to_string()),
             Some(caps) => {
+                let scheme = match caps.get(1).map(|scheme| scheme.as_str().to_lowercase()) {
+                    None => Scheme::Http,
+                    Some(scheme) if scheme == "http" => Scheme::Http,
+                    Some(scheme) if scheme == "https" => Scheme::Https,
+                    Some(scheme) => return Err(format!("unsupported scheme: {scheme}")),
+                };
                 let host = caps.get(2).unwrap().as_str();
                 let port_str = caps.get(3).unwrap().as_str();
-                let port_num: u16;
-                if port_str.len() > 1 {
-                    port_num = port_str[1..].parse::<u16>().unwrap();
+                let port_num: u16 = if port_str.len() > 1 {
+                    port_str[1..]
+                        .parse::<u16>()
+                        .map_err(|_| format!("invalid port: {}", &port_str[1..]))?
                 } else {
-                    port_num = 80;
-                }
+                    scheme.default_port()
+                };
                 let resource = match caps.get(4).unwrap().as_str() {
                     "" => "/",
                     res => res,
                 };
                 Ok(ParsedUrlHeader {
+                    scheme,
                     host: host.to_string(),
                     resource: resource.to_string(),
                     port: port_num,
-                    header: None,
+                    headers: vec![],
+                    default_headers: true,
+                    keep_alive: true,
+                    method: "GET".to_string(),
+                    body: None,
                 })
             }
         }
     }
     pub fn add_header(&mut self, header: String) -> Result<(), String> {
-        let header_regex =
-            Regex::new(r"^[a-zA-Z0-9-_ ]+: .*$").expect("unable to create headers regex");
-        match header_regex.is_match(&header) {
+        let (name, value) = parse_header(&header)?;
+        if name.eq_ignore_ascii_case("content-length")
+            || name.eq_ignore_ascii_case("transfer-encoding")
+        {
+            return Err(format!(
+                "header \"{name}\" is computed from the request body"
+            ));
+        }
+        self.headers.push((name, value));
+        Ok(())
+    }
+
+    fn has_header(&self, name: &str) -> bool {
+        self.headers
+            .iter()
+            .any(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn set_method(&mut self, method: &str) -> Result<(), String> {
+        let method_regex = Regex::new(r"^[a-zA-Z]+$").expect("unable to create method regex");
+        match method_regex.is_match(method) {
             true => {
-                self.header = Some(header);
+                self.method = method.to_uppercase();
                 Ok(())
             }
-            false => Err("header contain some error".to_string()),
+            false => Err(format!("invalid request method: {method}")),
+        }
+    }
+
+    pub fn set_body(&mut self, body: Vec<u8>) {
+        self.body = Some(body);
+    }
+
+    pub fn compile_request_head(&self) -> String {
+        let mut request = format!("{} {} HTTP/1.1\r\n", self.method, self.resource);
+        if self.default_headers && !self.has_header("host") {
+            request.push_str(&format!("Host: {}\r\n", self.host));
+        }
+        for (name, value) in &self.headers {
+            request.push_str(&format!("{name}: {value}\r\n"));
+        }
+        if !self.keep_alive && !self.has_header("connection") {
+            request.push_str("Connection: close\r\n");
         }
+        request
     }
 
-    pub fn compile_request(&mut self) -> String {
-        match &self.header {
-            Some(header) => {
-                format!(
-                    "GET {} HTTP/1.1\r\nHost: {}\r\n{}\r\n\r\n",
-                    self.resource, self.host, header
-                )
+    pub fn finish_request(&self, request: &mut Vec<u8>, body: Option<&[u8]>) {
+        match body {
+            Some(body) => {
+                request.extend_from_slice(format!("Content-Length: {}\r\n", body.len()).as_bytes())
             }
-            None => {
-                format!(
-                    "GET {} HTTP/1.1\r\nHost: {}\r\n\r\n",
-                    self.resource, self.host
-                )
+            None if ["POST", "PUT", "PATCH"].contains(&self.method.as_str()) => {
+                request.extend_from_slice(b"Content-Length: 0\r\n")
             }
+            None => {}
         }
+        request.extend_from_slice(b"\r\n");
+        if let Some(body) = body {
+            request.extend_from_slice(body);
+        }
+    }
+
+    pub fn compile_request(&mut self) -> Vec<u8> {
+        let mut request = self.compile_request_head().into_bytes();
+        self.finish_request(&mut request, self.body.as_deref());
+        request
     }
 }
 
 #[cfg(test)]
 mod test_parsing_url {
 
-    use super::ParsedUrlHeader;
+    use super::{parse_header, ParsedUrlHeader, Scheme};
 
     #[test]
     fn test_resource_parsing() {
         let raw_url = "127.0.0.1:8000/";
-        let parsed_url = ParsedUrlHeader::parse_url(&raw_url).unwrap();
+        let parsed_url = ParsedUrlHeader::parse_url(raw_url).unwrap();
         assert_eq!(parsed_url.resource, "/");
     }
     #[test]
     fn test_parsing_empty_resource() {
         let raw_url = "127.0.0.1:8000";
-        let parsed = ParsedUrlHeader::parse_url(&raw_url).unwrap();
+        let parsed = ParsedUrlHeader::parse_url(raw_url).unwrap();
         assert_eq!(parsed.resource, "/");
     }
 
+    #[test]
+    fn test_parsing_https_scheme() {
+        let parsed = ParsedUrlHeader::parse_url("https://api.example.com/v1/items").unwrap();
+        assert_eq!(parsed.scheme, Scheme::Https);
+        assert_eq!(parsed.host, "api.example.com");
+        assert_eq!(parsed.port, 443);
+        assert_eq!(parsed.resource, "/v1/items");
+    }
+
+    #[test]
+    fn test_parsing_explicit_port_with_scheme() {
+        let parsed = ParsedUrlHeader::parse_url("http://localhost:8443/").unwrap();
+        assert_eq!(parsed.scheme, Scheme::Http);
+        assert_eq!(parsed.host, "localhost");
+        assert_eq!(parsed.port, 8443);
+    }
+
+    #[test]
+    fn test_parsing_unsupported_scheme() {
+        assert!(ParsedUrlHeader::parse_url("ftp://localhost/").is_err());
+    }
+
     #[test]
     fn test_adding_header() {
         let raw_header = "x-Custom-Header: any value you want".to_string();
         let url = "127.0.0.1:8000";
         let mut parsed_url = ParsedUrlHeader::parse_url(url).unwrap();
         parsed_url.add_header(raw_header.clone()).unwrap();
-        assert_eq!(Some(raw_header), parsed_url.header);
+        assert_eq!(
+            vec![(
+                "x-Custom-Header".to_string(),
+                "any value you want".to_string()
+            )],
+            parsed_url.headers
+        );
+    }
+
+    #[test]
+    fn test_adding_multiple_headers() {
+        let mut parsed_url = ParsedUrlHeader::parse_url("127.0.0.1:8000/").unwrap();
+        parsed_url
+            .add_header("Accept: application/json".to_string())
+            .unwrap();
+        parsed_url
+            .add_header("Authorization:Bearer abc".to_string())
+            .unwrap();
+        assert_eq!(
+            b"GET / HTTP/1.1\r\nHost: 127.0.0.1\r\nAccept: application/json\r\nAuthorization: Bearer abc\r\n\r\n"
+                .to_vec(),
+            parsed_url.compile_request()
+        );
+    }
+
+    #[test]
+    fn test_overriding_host_header() {
+        let mut parsed_url = ParsedUrlHeader::parse_url("127.0.0.1:8000/").unwrap();
+        parsed_url
+            .add_header("host: api.internal".to_string())
+            .unwrap();
+        assert_eq!(
+            b"GET / HTTP/1.1\r\nhost: api.internal\r\n\r\n".to_vec(),
+            parsed_url.compile_request()
+        );
+    }
+
+    #[test]
+    fn test_suppressing_default_headers() {
+        let mut parsed_url = ParsedUrlHeader::parse_url("127.0.0.1:8000/").unwrap();
+        parsed_url.default_headers = false;
+        assert_eq!(
+            b"GET / HTTP/1.1\r\n\r\n".to_vec(),
+            parsed_url.compile_request()
+        );
+    }
+
+    #[test]
+    fn test_connection_close_without_keep_alive() {
+        let mut parsed_url = ParsedUrlHeader::parse_url("127.0.0.1:8000/").unwrap();
+        parsed_url.keep_alive = false;
+        assert_eq!(
+            b"GET / HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n".to_vec(),
+            parsed_url.compile_request()
+        );
+        parsed_url
+            .add_header("Connection: upgrade".to_string())
+            .unwrap();
+        assert!(!String::from_utf8(parsed_url.compile_request())
+            .unwrap()
+            .contains("close"));
+    }
+
+    #[test]
+    fn test_header_validation_errors() {
+        assert!(parse_header("no separator").is_err());
+        assert!(parse_header(": empty name").is_err());
+        assert!(parse_header("Bad Name: value").is_err());
+        assert!(parse_header("X-Value: line\r\nInjected: yes").is_err());
+        assert!(parse_header("X-Empty:").is_ok());
+        assert_eq!(
+            parse_header("X-Padded: \t spaced value \t").unwrap(),
+            ("X-Padded".to_string(), "spaced value".to_string())
+        );
+    }
+
+    #[test]
+    fn test_framing_headers_rejected() {
+        let mut parsed_url = ParsedUrlHeader::parse_url("127.0.0.1:8000/").unwrap();
+        assert!(parsed_url
+            .add_header("Content-Length: 10".to_string())
+            .is_err());
     }
 
     #[test]
@@ -109,8 +323,37 @@ mod test_parsing_url {
         let url = "127.0.0.1:8000/resource";
         let mut parsed_url = ParsedUrlHeader::parse_url(url).unwrap();
         assert_eq!(
-            "GET /resource HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n",
+            b"GET /resource HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n".to_vec(),
+            parsed_url.compile_request()
+        );
+    }
+
+    #[test]
+    fn test_compiling_request_with_body() {
+        let url = "127.0.0.1:8000/orders";
+        let mut parsed_url = ParsedUrlHeader::parse_url(url).unwrap();
+        parsed_url.set_method("post").unwrap();
+        parsed_url.set_body(b"{\"id\": 1}".to_vec());
+        assert_eq!(
+            b"POST /orders HTTP/1.1\r\nHost: 127.0.0.1\r\nContent-Length: 9\r\n\r\n{\"id\": 1}"
+                .to_vec(),
             parsed_url.compile_request()
         );
     }
+
+    #[test]
+    fn test_compiling_bodyless_post() {
+        let mut parsed_url = ParsedUrlHeader::parse_url("127.0.0.1:8000/").unwrap();
+        parsed_url.set_method("POST").unwrap();
+        assert_eq!(
+            b"POST / HTTP/1.1\r\nHost: 127.0.0.1\r\nContent-Length: 0\r\n\r\n".to_vec(),
+            parsed_url.compile_request()
+        );
+    }
+
+    #[test]
+    fn test_invalid_method() {
+        let mut parsed_url = ParsedUrlHeader::parse_url("127.0.0.1:8000/").unwrap();
+        assert!(parsed_url.set_method("GET /").is_err());
+    }
 }
@@ -8,3 +8,102 @@ pub fn format_received_data_value(received_data_value: usize) -> String {
         format!("{} B", received_data_value)
     }
 }
+
+pub fn format_timestamp(time: std::time::SystemTime) -> String {
+    let duration = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = duration.as_secs();
+    let days = (seconds / 86_400) as i64;
+    let seconds_of_day = seconds % 86_400;
+    // civil date from days since epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let shifted_days = days + 719_468;
+    let era = shifted_days.div_euclid(146_097);
+    let day_of_era = shifted_days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3_600,
+        seconds_of_day % 3_600 / 60,
+        seconds_of_day % 60,
+        duration.subsec_millis()
+    )
+}
+
+pub fn parse_duration(value: &str) -> Result<std::time::Duration, String> {
+    let value = value.trim();
+    let split_at = value
+        .find(|character: char| !character.is_ascii_digit() && character != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split_at);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration: {value}"))?;
+    let seconds = match unit {
+        "us" => number / 1_000_000.0,
+        "ms" => number / 1_000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3_600.0,
+        _ => {
+            return Err(format!(
+                "invalid duration unit in {value}, use us, ms, s, m or h"
+            ))
+        }
+    };
+    std::time::Duration::try_from_secs_f64(seconds)
+        .map_err(|_| format!("invalid duration: {value}"))
+}
+
+// splits total into parts that differ by at most one, larger parts first
+pub fn split_evenly(total: u64, parts: usize) -> Vec<u64> {
+    (0..parts as u64)
+        .map(|part| total / parts as u64 + u64::from(part < total % parts as u64))
+        .collect()
+}
+
+#[cfg(test)]
+mod test_utils {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{format_timestamp, parse_duration, split_evenly};
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        let time = UNIX_EPOCH + Duration::from_millis(1_709_210_096_789);
+        assert_eq!(format_timestamp(time), "2024-02-29T12:34:56.789Z");
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("100us"), Ok(Duration::from_micros(100)));
+        assert!(parse_duration("ms").is_err());
+        assert!(parse_duration("5 days").is_err());
+    }
+
+    #[test]
+    fn test_split_evenly() {
+        assert_eq!(split_evenly(10, 3), [4, 3, 3]);
+        assert_eq!(split_evenly(2, 4), [1, 1, 0, 0]);
+        assert_eq!(split_evenly(9, 3), [3, 3, 3]);
+        assert!(split_evenly(5, 0).is_empty());
+    }
+}